@@ -0,0 +1,124 @@
+//! Axis-aligned bounding boxes.
+
+use crate::vec3::{Point3, Vec3};
+
+/// The smallest thickness a box is given, so that flat shapes such as quads in
+/// an axis plane still have a volume rays can pass through.
+const MIN_THICKNESS: f64 = 1e-4;
+
+/// A box with sides parallel to the axes, from `min` to `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    /// The box spanned by two opposite corners, in either order. Flat boxes are
+    /// padded to a minimum thickness.
+    pub fn new(a: Point3, b: Point3) -> Aabb {
+        let (mut min, mut max) = (a.min(b), a.max(b));
+        let pad = |min: &mut f64, max: &mut f64| {
+            if *max - *min < MIN_THICKNESS {
+                let center = (*min + *max) * 0.5;
+                *min = center - MIN_THICKNESS * 0.5;
+                *max = center + MIN_THICKNESS * 0.5;
+            }
+        };
+        pad(&mut min.x, &mut max.x);
+        pad(&mut min.y, &mut max.y);
+        pad(&mut min.z, &mut max.z);
+        Aabb { min, max }
+    }
+
+    /// The smallest box around all of `points`, or `None` if there are none.
+    pub fn around(points: &[Point3]) -> Option<Aabb> {
+        let (first, rest) = points.split_first()?;
+        let (min, max) = rest.iter().fold((*first, *first), |(min, max), &p| (min.min(p), max.max(p)));
+        Some(Aabb::new(min, max))
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The smallest box containing this one and `point`.
+    pub fn grow(self, point: Point3) -> Aabb {
+        Aabb {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// The axis along which the box is longest: 0, 1 or 2 for x, y or z.
+    pub fn longest_axis(&self) -> usize {
+        let size = self.size();
+        if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Whether a ray from `origin` passes through the box with `t_min < t < t_max`.
+    /// `inverse_direction` is one over each component of the ray direction, which
+    /// callers testing many boxes against one ray compute only once.
+    pub fn hit(&self, origin: Point3, inverse_direction: Vec3, t_min: f64, t_max: f64) -> bool {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - origin[axis]) * inverse_direction[axis];
+            let t1 = (self.max[axis] - origin[axis]) * inverse_direction[axis];
+            // `max` and `min` ignore the NaNs of rays lying in a side's plane.
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_hit_boxes_they_pass_through() {
+        let aabb = Aabb::new(Point3::splat(1.0), Point3::splat(-1.0));
+        assert_eq!(aabb.min, Point3::splat(-1.0));
+        assert_eq!(aabb.surface_area(), 24.0);
+
+        let inverse = |d: Vec3| Vec3::new(1.0 / d.x, 1.0 / d.y, 1.0 / d.z);
+        let origin = Point3::new(0.0, 0.0, -5.0);
+        assert!(aabb.hit(origin, inverse(Vec3::new(0.0, 0.0, 1.0)), 0.0, f64::INFINITY));
+        assert!(!aabb.hit(origin, inverse(Vec3::new(0.0, 0.0, -1.0)), 0.0, f64::INFINITY));
+        assert!(!aabb.hit(origin, inverse(Vec3::new(0.0, 0.0, 1.0)), 0.0, 3.0));
+        assert!(!aabb.hit(origin, inverse(Vec3::new(0.0, 1.0, 1.0)), 0.0, f64::INFINITY));
+
+        // A square in the z = 0 plane still has some thickness.
+        let flat = Aabb::around(&[Point3::new(-1.0, -1.0, 0.0), Point3::new(1.0, 1.0, 0.0)]).unwrap();
+        assert!(flat.size().z > 0.0);
+        assert!(flat.hit(origin, inverse(Vec3::new(0.0, 0.0, 1.0)), 0.0, f64::INFINITY));
+        assert_eq!(flat.longest_axis(), 0);
+    }
+}
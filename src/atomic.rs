@@ -0,0 +1,241 @@
+//! Output files that appear all at once.
+//!
+//! Writing straight over the target leaves a truncated image behind if the process
+//! dies half way. An [`AtomicFile`] writes to a hidden temporary file next to the
+//! target and only renames it into place once everything has been written and
+//! synced, so readers see either the old file or the complete new one.
+
+use crate::error::{Error, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// What to do when the output file already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overwrite {
+    /// Replace the existing file.
+    #[default]
+    Always,
+    /// Fail with [`Error::OutputExists`] and leave the existing file alone.
+    Never,
+}
+
+impl Overwrite {
+    /// Fails early if `path` exists and may not be replaced, so that callers can
+    /// skip work whose result would be refused anyway.
+    pub fn check(self, path: &Path) -> Result<()> {
+        if self == Overwrite::Never && path.exists() {
+            return Err(Error::OutputExists(path.to_path_buf()));
+        }
+        Ok(())
+    }
+}
+
+/// A file that is written under a temporary name and moved into place by
+/// [`commit`](AtomicFile::commit). Dropping it without committing removes the
+/// temporary file and leaves the target untouched.
+pub struct AtomicFile {
+    path: PathBuf,
+    temp: PathBuf,
+    overwrite: Overwrite,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// Creates the temporary file for `path`, creating any missing parent
+    /// directories first.
+    pub fn create<P: AsRef<Path>>(path: P, overwrite: Overwrite) -> Result<AtomicFile> {
+        let path = path.as_ref().to_path_buf();
+        overwrite.check(&path)?;
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(dir)?;
+
+        // The temporary file must be in the same directory, since a rename is only
+        // atomic within one file system.
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name"))?;
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let temp = dir.join(format!(
+            ".{}.{}-{}.tmp",
+            name.to_string_lossy(),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+
+        Ok(AtomicFile {
+            path,
+            temp,
+            overwrite,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// The final path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flushes and syncs the written data and moves it to the final path.
+    pub fn commit(mut self) -> Result<()> {
+        let file = self.file.take().expect("an uncommitted file is open");
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        drop(file);
+
+        let moved = match self.overwrite {
+            Overwrite::Always => fs::rename(&self.temp, &self.path),
+            // A hard link fails if the target exists, which closes the gap between
+            // checking for the file and replacing it.
+            Overwrite::Never => match fs::hard_link(&self.temp, &self.path) {
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    return Err(Error::OutputExists(self.path.clone()));
+                }
+                Ok(()) => fs::remove_file(&self.temp),
+                // Some file systems have no hard links: vfat, exFAT and some network
+                // file systems refuse with EPERM rather than ENOTSUP. A genuine
+                // permission problem makes the reservation fail too, and is reported then.
+                Err(e) if matches!(e.kind(), io::ErrorKind::Unsupported | io::ErrorKind::PermissionDenied) => {
+                    return reserve_and_rename(&self.temp, &self.path);
+                }
+                Err(e) => Err(e),
+            },
+        };
+        moved.map_err(Error::from)
+    }
+}
+
+/// Moves `temp` to `path` only if nothing is there, without hard links: creating
+/// `path` exclusively claims the name, and the rename then replaces that empty
+/// placeholder. Readers may briefly see the empty file, but an existing file is
+/// never replaced.
+fn reserve_and_rename(temp: &Path, path: &Path) -> Result<()> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(Error::OutputExists(path.to_path_buf())),
+        Err(e) => return Err(e.into()),
+        Ok(_) => {}
+    }
+    fs::rename(temp, path).map_err(|e| {
+        let _ = fs::remove_file(path);
+        Error::from(e)
+    })
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().expect("an uncommitted file is open").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().expect("an uncommitted file is open").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // After a successful commit the temporary name is already gone, so failing
+        // to remove it is expected and harmless.
+        self.file.take();
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for each test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("output-image-atomic-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> =
+            fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn commit_moves_the_data_into_place() {
+        let dir = scratch_dir("commit");
+        let path = dir.join("nested").join("out.txt");
+        let mut file = AtomicFile::create(&path, Overwrite::Always).unwrap();
+        file.write_all(b"new").unwrap();
+        // Nothing is visible under the final name until the commit.
+        assert!(!path.exists());
+        file.commit().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(entries(&dir.join("nested")), ["out.txt"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dropping_without_commit_removes_the_temporary_file() {
+        let dir = scratch_dir("drop");
+        let path = dir.join("out.txt");
+        fs::write(&path, b"old").unwrap();
+        {
+            let mut file = AtomicFile::create(&path, Overwrite::Always).unwrap();
+            file.write_all(b"partial").unwrap();
+            assert_eq!(entries(&dir).len(), 2);
+        }
+        assert_eq!(entries(&dir), ["out.txt"]);
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn never_refuses_to_clobber_an_existing_file() {
+        let dir = scratch_dir("never");
+        let path = dir.join("out.txt");
+        fs::write(&path, b"old").unwrap();
+        assert!(matches!(AtomicFile::create(&path, Overwrite::Never), Err(Error::OutputExists(_))));
+
+        // A file that appears while the new one is being written is kept too.
+        fs::remove_file(&path).unwrap();
+        let mut file = AtomicFile::create(&path, Overwrite::Never).unwrap();
+        file.write_all(b"new").unwrap();
+        fs::write(&path, b"raced").unwrap();
+        assert!(matches!(file.commit(), Err(Error::OutputExists(_))));
+        assert_eq!(fs::read(&path).unwrap(), b"raced");
+        assert_eq!(entries(&dir), ["out.txt"]);
+
+        fs::remove_file(&path).unwrap();
+        let mut file = AtomicFile::create(&path, Overwrite::Never).unwrap();
+        file.write_all(b"new").unwrap();
+        file.commit().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn the_fallback_without_hard_links_claims_the_name_first() {
+        let dir = scratch_dir("reserve");
+        let (temp, path) = (dir.join("temp"), dir.join("out.txt"));
+        fs::write(&temp, b"new").unwrap();
+        fs::write(&path, b"old").unwrap();
+        assert!(matches!(reserve_and_rename(&temp, &path), Err(Error::OutputExists(_))));
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+
+        fs::remove_file(&path).unwrap();
+        reserve_and_rename(&temp, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(entries(&dir), ["out.txt"]);
+
+        // A rename that fails gives the name back.
+        let missing = dir.join("missing");
+        let other = dir.join("other.txt");
+        assert!(matches!(reserve_and_rename(&missing, &other), Err(Error::Io(_))));
+        assert_eq!(entries(&dir), ["out.txt"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,369 @@
+//! A bounding volume hierarchy, so that rays only test the objects near them.
+//!
+//! The tree is built top down. Each node is split where the surface area heuristic
+//! (SAH) predicts the cheapest traversal, choosing among the boundaries of a few
+//! bins along each axis rather than between every pair of objects. It is stored
+//! flattened in depth-first order: the first child of a node directly follows it,
+//! so that traversal mostly walks forward through memory.
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of candidate split positions per axis, plus one.
+const BINS: usize = 12;
+/// Nodes with this many objects or fewer become leaves unless splitting is
+/// predicted to be cheaper.
+const MAX_LEAF_SIZE: usize = 4;
+/// The cost of testing a ray against a node's box, relative to testing an object.
+const TRAVERSAL_COST: f64 = 1.0;
+/// No path through the tree is longer, which bounds the traversal stack.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    bounds: Aabb,
+    /// For leaves the index of their first object, for interior nodes the index of
+    /// their second child.
+    offset: u32,
+    /// The number of objects in a leaf, or 0 for interior nodes.
+    count: u32,
+    /// The axis interior nodes were split along.
+    axis: u8,
+}
+
+/// An object waiting to be placed in the tree.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    index: usize,
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+/// A group of objects, like a [`HittableList`](crate::hittable::HittableList),
+/// that answers the same queries in logarithmic rather than linear time.
+///
+/// Unbounded objects such as planes cannot be placed in the tree, and are tested
+/// against every ray.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Ordered so that every leaf covers a contiguous range.
+    objects: Vec<Box<dyn Hittable>>,
+    unbounded: Vec<Box<dyn Hittable>>,
+    depth: usize,
+    rays: AtomicU64,
+    node_tests: AtomicU64,
+    object_tests: AtomicU64,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Bvh {
+        let (mut entries, mut slots, mut unbounded) = (Vec::new(), Vec::new(), Vec::new());
+        for object in objects {
+            match object.bounding_box() {
+                Some(bounds) => {
+                    entries.push(Entry {
+                        index: slots.len(),
+                        bounds,
+                        centroid: bounds.centroid(),
+                    });
+                    slots.push(Some(object));
+                }
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(2 * entries.len());
+        let depth = if entries.is_empty() {
+            0
+        } else {
+            build(&mut nodes, &mut entries, 0, 1)
+        };
+        let objects = entries
+            .iter()
+            .map(|entry| slots[entry.index].take().expect("every object is placed once"))
+            .collect();
+        Bvh {
+            nodes,
+            objects,
+            unbounded,
+            depth,
+            rays: AtomicU64::new(0),
+            node_tests: AtomicU64::new(0),
+            object_tests: AtomicU64::new(0),
+        }
+    }
+
+    /// The shape of the tree, and how much work the rays traced so far have done.
+    pub fn stats(&self) -> BvhStats {
+        BvhStats {
+            objects: self.objects.len() + self.unbounded.len(),
+            nodes: self.nodes.len(),
+            leaves: self.nodes.iter().filter(|node| node.count > 0).count(),
+            depth: self.depth,
+            rays: self.rays.load(Ordering::Relaxed),
+            node_tests: self.node_tests.load(Ordering::Relaxed),
+            object_tests: self.object_tests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Adds the subtree for `entries` to `nodes`, and returns its depth. `first` is
+/// the index of the first entry among all objects.
+fn build(nodes: &mut Vec<Node>, entries: &mut [Entry], first: usize, depth: usize) -> usize {
+    let index = nodes.len();
+    let bounds = entries[1..].iter().fold(entries[0].bounds, |all, entry| all.union(entry.bounds));
+    let leaf = Node {
+        bounds,
+        offset: first as u32,
+        count: entries.len() as u32,
+        axis: 0,
+    };
+    nodes.push(leaf);
+    if entries.len() == 1 || depth >= MAX_DEPTH {
+        return depth;
+    }
+
+    let (axis, split) = match split(entries, bounds) {
+        Some((axis, split)) => (axis, split),
+        None => return depth,
+    };
+    let left = build(nodes, &mut entries[..split], first, depth + 1);
+    nodes[index] = Node {
+        offset: nodes.len() as u32,
+        count: 0,
+        axis: axis as u8,
+        ..leaf
+    };
+    left.max(build(nodes, &mut entries[split..], first + split, depth + 1))
+}
+
+/// Partitions `entries` at the split with the lowest predicted cost, returning the
+/// axis and the number of entries on the near side, or `None` if a leaf is better.
+fn split(entries: &mut [Entry], bounds: Aabb) -> Option<(usize, usize)> {
+    let start = Aabb {
+        min: entries[0].centroid,
+        max: entries[0].centroid,
+    };
+    let centroids = entries[1..].iter().fold(start, |all, entry| all.grow(entry.centroid));
+    let bin = |axis: usize, entry: &Entry| {
+        let (low, high) = (centroids.min[axis], centroids.max[axis]);
+        let position = (entry.centroid[axis] - low) / (high - low);
+        ((position * BINS as f64) as usize).min(BINS - 1)
+    };
+
+    // The cost of each split is the chance of a ray through this node hitting each
+    // side, judged by surface area, times the number of objects there.
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        if centroids.max[axis] - centroids.min[axis] <= 0.0 {
+            continue;
+        }
+        let mut bins: [(usize, Option<Aabb>); BINS] = [(0, None); BINS];
+        for entry in entries.iter() {
+            let (count, aabb) = &mut bins[bin(axis, entry)];
+            *count += 1;
+            *aabb = Some(aabb.map_or(entry.bounds, |aabb| aabb.union(entry.bounds)));
+        }
+        // The cost of everything up to and including each bin, from either end.
+        let (mut below, mut above) = ([0.0; BINS], [0.0; BINS]);
+        let (mut low, mut high) = ((0, None), (0, None));
+        for i in 0..BINS {
+            below[i] = accumulate(&mut low, bins[i]);
+            above[BINS - 1 - i] = accumulate(&mut high, bins[BINS - 1 - i]);
+        }
+        for boundary in 1..BINS {
+            let cost = below[boundary - 1] + above[boundary];
+            if best.is_none_or(|(best, _, _)| cost < best) {
+                best = Some((cost, axis, boundary));
+            }
+        }
+    }
+
+    let leaf_cost = entries.len() as f64;
+    let (axis, boundary) = match best {
+        Some((cost, axis, boundary)) => {
+            let cost = TRAVERSAL_COST + cost / bounds.surface_area();
+            if cost >= leaf_cost && entries.len() <= MAX_LEAF_SIZE {
+                return None;
+            }
+            (axis, boundary)
+        }
+        // All centroids coincide, so no plane separates them; halve the list.
+        None if entries.len() <= MAX_LEAF_SIZE => return None,
+        None => return Some((0, entries.len() / 2)),
+    };
+
+    let mut near = 0;
+    for i in 0..entries.len() {
+        if bin(axis, &entries[i]) < boundary {
+            entries.swap(i, near);
+            near += 1;
+        }
+    }
+    Some((axis, near))
+}
+
+/// Adds a bin to a running total, and returns the total's cost.
+fn accumulate(total: &mut (usize, Option<Aabb>), (count, bounds): (usize, Option<Aabb>)) -> f64 {
+    total.0 += count;
+    total.1 = match (total.1, bounds) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, b) => a.or(b),
+    };
+    total.0 as f64 * total.1.map_or(0.0, |aabb| aabb.surface_area())
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for object in &self.unbounded {
+            if let Some(hit) = object.hit(ray, t_min, t_max) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let d = ray.direction;
+        let inverse_direction = Vec3::new(1.0 / d.x, 1.0 / d.y, 1.0 / d.z);
+        let (mut stack, mut pending) = ([0u32; MAX_DEPTH], 0);
+        let (mut node_tests, mut object_tests) = (0, self.unbounded.len() as u64);
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index as usize];
+            node_tests += 1;
+            if node.bounds.hit(ray.origin, inverse_direction, t_min, t_max) {
+                if node.count == 0 {
+                    // Visit the nearer child first, so that its hits can rule out
+                    // the farther one.
+                    let (near, far) = if d[node.axis as usize] < 0.0 {
+                        (node.offset, index + 1)
+                    } else {
+                        (index + 1, node.offset)
+                    };
+                    stack[pending] = far;
+                    pending += 1;
+                    index = near;
+                    continue;
+                }
+                let range = node.offset as usize..(node.offset + node.count) as usize;
+                for object in &self.objects[range] {
+                    object_tests += 1;
+                    if let Some(hit) = object.hit(ray, t_min, t_max) {
+                        t_max = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+            }
+            if pending == 0 {
+                break;
+            }
+            pending -= 1;
+            index = stack[pending];
+        }
+
+        self.rays.fetch_add(1, Ordering::Relaxed);
+        self.node_tests.fetch_add(node_tests, Ordering::Relaxed);
+        self.object_tests.fetch_add(object_tests, Ordering::Relaxed);
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if self.unbounded.is_empty() {
+            self.nodes.first().map(|root| root.bounds)
+        } else {
+            None
+        }
+    }
+}
+
+/// The shape of a [`Bvh`] and the work done traversing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BvhStats {
+    pub objects: usize,
+    pub nodes: usize,
+    pub leaves: usize,
+    /// The number of nodes on the longest path from the root to a leaf.
+    pub depth: usize,
+    pub rays: u64,
+    pub node_tests: u64,
+    pub object_tests: u64,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BVH: {} objects in {} nodes ({} leaves), depth {}",
+            self.objects, self.nodes, self.leaves, self.depth
+        )?;
+        if self.rays > 0 {
+            let per_ray = |tests: u64| tests as f64 / self.rays as f64;
+            write!(
+                f,
+                "; {} rays, {:.1} box and {:.1} object tests per ray",
+                self.rays,
+                per_ray(self.node_tests),
+                per_ray(self.object_tests)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HittableList;
+    use crate::rng::Rng;
+    use crate::shapes::{Plane, Sphere, Triangle};
+    use crate::vec3::Point3;
+
+    /// A plane under a cloud of small spheres and triangles.
+    fn cloud() -> HittableList {
+        let mut rng = Rng::new(3);
+        let mut point = |scale: f64| {
+            let mut coordinate = || rng.next_f64() - 0.5;
+            Point3::new(coordinate(), coordinate(), coordinate()) * scale
+        };
+        let mut list = HittableList::new();
+        list.add(Plane::new(Point3::new(0.0, -6.0, 0.0), Vec3::new(0.0, 1.0, 0.0)));
+        for _ in 0..250 {
+            list.add(Sphere::new(point(10.0), 0.2));
+            let center = point(10.0);
+            list.add(Triangle::new(center + point(0.5), center + point(0.5), center + point(0.5)));
+        }
+        list
+    }
+
+    #[test]
+    fn finds_the_same_hits_as_a_list() {
+        let (list, bvh) = (cloud(), Bvh::new(cloud().objects));
+        let mut rng = Rng::new(4);
+        let mut point = |scale: f64| {
+            let mut coordinate = || rng.next_f64() - 0.5;
+            Point3::new(coordinate(), coordinate(), coordinate()) * scale
+        };
+        let rays = 2000;
+        for _ in 0..rays {
+            let ray = Ray::new(point(30.0), point(1.0));
+            let expected = list.hit(&ray, 1e-3, f64::INFINITY).map(|hit| (hit.t, hit.normal));
+            assert_eq!(bvh.hit(&ray, 1e-3, f64::INFINITY).map(|hit| (hit.t, hit.normal)), expected);
+        }
+
+        let stats = bvh.stats();
+        assert_eq!(stats.objects, 501);
+        assert_eq!(stats.nodes, 2 * stats.leaves - 1);
+        assert!(stats.depth < 30, "depth {}", stats.depth);
+        assert_eq!(stats.rays, rays);
+        // Far fewer tests than the 501 per ray of the list.
+        assert!(stats.object_tests < 50 * rays, "{}", stats);
+    }
+}
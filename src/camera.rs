@@ -0,0 +1,99 @@
+//! A pinhole camera that turns image positions into rays.
+
+use crate::error::{Error, Result};
+use crate::ray::Ray;
+use crate::shader::ShadeContext;
+use crate::vec3::{Point3, Vec3};
+
+/// A pinhole camera at `position` looking towards `look_at`, with `up` giving the
+/// roll. The horizontal field of view follows from the aspect ratio of the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: Point3,
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    /// Half the height of the image plane at distance 1.
+    half_height: f64,
+    /// Unit vectors to the right, up and backwards as seen through the camera.
+    right: Vec3,
+    upward: Vec3,
+    backward: Vec3,
+}
+
+impl Camera {
+    /// Fails if `look_at` is at `position`, since there is then no view direction,
+    /// or if `up` points along the view, since the roll is then undefined.
+    pub fn new(position: Point3, look_at: Point3, up: Vec3, fov: f64) -> Result<Camera> {
+        if (position - look_at).near_zero() {
+            return Err(Error::Usage("'look-at' must differ from 'position'".to_string()));
+        }
+        if up.cross(position - look_at).near_zero() {
+            return Err(Error::Usage("'up' must not be parallel to the view direction".to_string()));
+        }
+        let backward = (position - look_at).normalize();
+        let right = up.cross(backward).normalize();
+        Ok(Camera {
+            position,
+            fov,
+            half_height: (fov.to_radians() / 2.0).tan(),
+            right,
+            upward: backward.cross(right),
+            backward,
+        })
+    }
+
+    /// The ray through the point `(s, t)` of the image plane, where `(0, 0)` is the
+    /// bottom left corner and `(1, 1)` the top right.
+    pub fn ray(&self, s: f64, t: f64, aspect_ratio: f64) -> Ray {
+        let half_width = self.half_height * aspect_ratio;
+        let direction = -self.backward
+            + self.right * ((2.0 * s - 1.0) * half_width)
+            + self.upward * ((2.0 * t - 1.0) * self.half_height);
+        Ray::new(self.position, direction)
+    }
+
+    /// The ray through the point being shaded.
+    pub fn ray_for(&self, ctx: &ShadeContext) -> Ray {
+        self.ray(ctx.u, 1.0 - ctx.v, ctx.aspect_ratio())
+    }
+}
+
+impl Default for Camera {
+    /// The book's camera: at the origin looking down the negative z axis, with an
+    /// image plane two units high at distance 1.
+    fn default() -> Self {
+        Camera::new(Point3::ZERO, Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0), 90.0)
+            .expect("the default view is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_span_the_field_of_view() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let camera = Camera::new(Point3::new(1.0, 2.0, 3.0), Point3::new(1.0, 2.0, 0.0), up, 90.0).unwrap();
+        let center = camera.ray(0.5, 0.5, 2.0);
+        assert_eq!(center.origin, Point3::new(1.0, 2.0, 3.0));
+        assert!((center.direction - Vec3::new(0.0, 0.0, -1.0)).near_zero());
+
+        // At 90 degrees the top edge is 45 degrees up; the sides are twice as far out.
+        let top_right = camera.ray(1.0, 1.0, 2.0).direction;
+        assert!((top_right - Vec3::new(2.0, 1.0, -1.0)).near_zero());
+
+        // Looking along +x, the right of the image is +z.
+        let sideways = Camera::new(Point3::ZERO, Point3::new(1.0, 0.0, 0.0), up, 90.0).unwrap();
+        assert!((sideways.ray(1.0, 0.5, 1.0).direction - Vec3::new(1.0, 0.0, 1.0)).near_zero());
+    }
+
+    #[test]
+    fn rejects_views_without_a_direction_or_roll() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let at_itself = Camera::new(Point3::splat(1.0), Point3::splat(1.0), up, 90.0);
+        assert!(matches!(at_itself, Err(Error::Usage(_))));
+        let straight_down = Camera::new(Point3::new(0.0, 5.0, 0.0), Point3::ZERO, up, 90.0);
+        assert!(matches!(straight_down, Err(Error::Usage(_))));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::atomic::{AtomicFile, Overwrite};
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::format::OutputFormat;
+use crate::quantize::{Quantizer, Rgb16Image};
+use crate::{exr, netpbm, pfm, png, radiance};
+use image::tiff::TiffEncoder;
+use image::{ColorType, DynamicImage, EncodableLayout, ImageFormat, RgbImage};
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+/// A linear floating point RGB framebuffer.
+///
+/// Colors are kept at full precision while rendering and only turned into integer
+/// samples by a [`Quantizer`] when the canvas is saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    /// The largest width or height accepted by [`check_size`](Canvas::check_size).
+    pub const MAX_DIMENSION: u32 = 65_535;
+    /// The largest pixel count accepted by [`check_size`](Canvas::check_size). The
+    /// floating point canvas alone takes 24 bytes per pixel.
+    pub const MAX_PIXELS: u64 = 1 << 26;
+
+    /// Checks that a canvas of this size is non-empty and can reasonably be held in
+    /// memory.
+    pub fn check_size(width: u32, height: u32) -> Result<()> {
+        let invalid = |reason: String| Err(Error::InvalidDimensions { width, height, reason });
+        if width == 0 || height == 0 {
+            return invalid("dimensions must be non-zero".to_string());
+        }
+        if width > Self::MAX_DIMENSION || height > Self::MAX_DIMENSION {
+            return invalid(format!("dimensions must not exceed {}", Self::MAX_DIMENSION));
+        }
+        if width as u64 * height as u64 > Self::MAX_PIXELS {
+            return invalid(format!("at most {} pixels are supported", Self::MAX_PIXELS));
+        }
+        Ok(())
+    }
+
+    /// Creates a black canvas.
+    pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![Color::BLACK; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel ({}, {}) is outside the canvas", x, y);
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let i = self.index(x, y);
+        self.pixels[i] = color;
+    }
+
+    /// Adds `color` to the pixel, for accumulating several samples or passes.
+    pub fn add_to_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let i = self.index(x, y);
+        self.pixels[i] += color;
+    }
+
+    /// Multiplies every pixel by `t`, e.g. to average accumulated samples.
+    pub fn scale(&mut self, t: f64) {
+        for pixel in &mut self.pixels {
+            *pixel *= t;
+        }
+    }
+
+    /// Replaces every pixel with `f` of its color.
+    pub fn map<F>(&mut self, f: F)
+    where
+        F: Fn(Color) -> Color,
+    {
+        for pixel in &mut self.pixels {
+            *pixel = f(*pixel);
+        }
+    }
+
+    /// Sets every pixel to the color returned by `f(x, y)`, row by row from the top left.
+    pub fn fill<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u32, u32) -> Color,
+    {
+        let width = self.width as usize;
+        for (i, pixel) in self.pixels.iter_mut().enumerate() {
+            *pixel = f((i % width) as u32, (i / width) as u32);
+        }
+    }
+
+    /// All pixels in row-major order.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    /// Quantizes the canvas to 8 bits per channel.
+    pub fn to_rgb8(&self, quantizer: &Quantizer) -> RgbImage {
+        quantizer.to_rgb8(self.width, self.height, &self.pixels)
+    }
+
+    /// Quantizes the canvas to 16 bits per channel.
+    pub fn to_rgb16(&self, quantizer: &Quantizer) -> Rgb16Image {
+        quantizer.to_rgb16(self.width, self.height, &self.pixels)
+    }
+
+    /// Saves the canvas, picking the file format from the extension of `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P, quantizer: &Quantizer) -> Result<()> {
+        self.save_as(path, None, quantizer, Overwrite::Always)
+    }
+
+    /// Saves the canvas in `format`, or the one implied by the extension of `path`.
+    ///
+    /// The file is written through an [`AtomicFile`], so an interrupted save never
+    /// leaves a partial image at `path`. Missing parent directories are created.
+    pub fn save_as<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: Option<OutputFormat>,
+        quantizer: &Quantizer,
+        overwrite: Overwrite,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        match format.or_else(|| OutputFormat::from_path(path)) {
+            Some(format) => {
+                let mut file = AtomicFile::create(path, overwrite)?;
+                self.write_to(&mut file, format, quantizer)?;
+                file.commit()
+            }
+            // Other extensions are left to the image crate, which knows JPEG, BMP and more.
+            None => {
+                let format = ImageFormat::from_path(path)?;
+                let mut file = AtomicFile::create(path, overwrite)?;
+                DynamicImage::ImageRgb8(self.to_rgb8(quantizer)).write_to(&mut file, format)?;
+                file.commit()
+            }
+        }
+    }
+
+    /// Encodes the canvas into any writer, such as stdout. Floating point formats
+    /// store the linear values as they are and do not use `quantizer`.
+    ///
+    /// PNG and TIFF are written with 16-bit samples when the quantizer keeps more than
+    /// 8 bits; other integer formats are reduced to 8 bits.
+    pub fn write_to<W: Write>(&self, w: &mut W, format: OutputFormat, quantizer: &Quantizer) -> Result<()> {
+        match format {
+            OutputFormat::Png if quantizer.is_wide() => {
+                let image = self.to_rgb16(quantizer);
+                png::write(w, image.as_bytes(), self.width, self.height, ColorType::Rgb16, quantizer.transfer)?;
+            }
+            OutputFormat::Png => {
+                let image = self.to_rgb8(quantizer);
+                png::write(w, &image, self.width, self.height, ColorType::Rgb8, quantizer.transfer)?;
+            }
+            OutputFormat::Tiff => {
+                // The TIFF encoder needs to seek back to patch offsets.
+                let mut tiff = Cursor::new(Vec::new());
+                if quantizer.is_wide() {
+                    let image = self.to_rgb16(quantizer);
+                    TiffEncoder::new(&mut tiff).encode(image.as_bytes(), self.width, self.height, ColorType::Rgb16)?;
+                } else {
+                    let image = self.to_rgb8(quantizer);
+                    TiffEncoder::new(&mut tiff).encode(&image, self.width, self.height, ColorType::Rgb8)?;
+                }
+                w.write_all(tiff.get_ref())?;
+                w.flush()?;
+            }
+            OutputFormat::Netpbm(format) => netpbm::write(w, &self.to_rgb8(quantizer), format)?,
+            OutputFormat::Radiance => radiance::write(w, self)?,
+            OutputFormat::Pfm => pfm::write(w, self)?,
+            OutputFormat::OpenExr(options) => exr::write(w, self, options)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantize::Rounding;
+    use crate::Generator;
+    use std::{fs, process};
+
+    #[test]
+    fn saved_png_decodes_to_the_quantized_pixels() {
+        let canvas = crate::render(Generator::Gradient, 6, 4);
+        let quantizer = Quantizer::default();
+        let dir = std::env::temp_dir().join(format!("output-image-canvas-{}", process::id()));
+        let path = dir.join("gradient.png");
+        canvas.save(&path, &quantizer).unwrap();
+
+        let decoded = image::open(&path).unwrap().to_rgb();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(decoded.dimensions(), (6, 4));
+        assert_eq!(decoded, canvas.to_rgb8(&quantizer));
+        // The book's gradient: red grows along each row and green down the image.
+        assert_eq!(decoded.get_pixel(0, 0).0, [0, 0, 63]);
+        assert_eq!(decoded.get_pixel(5, 3).0, [255, 255, 63]);
+    }
+
+    #[test]
+    fn wide_png_and_tiff_decode_to_16_bit_samples() {
+        let mut canvas = Canvas::new(5, 3);
+        canvas.fill(|x, y| Color::new(x as f64 / 4.0, y as f64 / 2.0, 0.123_456));
+        let quantizer = Quantizer {
+            bits: 16,
+            rounding: Rounding::Nearest,
+            ..Quantizer::default()
+        };
+        for &(format, image_format) in &[(OutputFormat::Png, ImageFormat::Png), (OutputFormat::Tiff, ImageFormat::Tiff)] {
+            let mut bytes = Vec::new();
+            canvas.write_to(&mut bytes, format, &quantizer).unwrap();
+            let decoded = match image::load_from_memory_with_format(&bytes, image_format).unwrap() {
+                DynamicImage::ImageRgb16(image) => image,
+                other => panic!("{:?} decoded as {:?}", format, other.color()),
+            };
+            assert_eq!(decoded, canvas.to_rgb16(&quantizer), "{:?}", format);
+            // 0.123456 lands between two 8-bit levels, which would expand to a
+            // multiple of 257.
+            assert_eq!(decoded.get_pixel(0, 0).0, [0, 0, 8091], "{:?}", format);
+            assert_eq!(decoded.get_pixel(2, 1).0, [32768, 32768, 8091], "{:?}", format);
+            assert_eq!(decoded.get_pixel(4, 2).0, [65535, 65535, 8091], "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+
+use output_image::progress::Style;
+use output_image::sampling::Sampling;
+use output_image::{
+    exr, netpbm, params, Canvas, Error, Generator, OutputFormat, Overwrite, Params, Quantizer, Transfer,
+};
+
+pub const DEFAULT_WIDTH: u32 = 256;
+pub const DEFAULT_HEIGHT: u32 = 256;
+pub const DEFAULT_OUTPUT: &str = "image.png";
+
+pub const USAGE: &str = "\
+Usage: output-image [COMMAND] [OPTIONS]
+
+Commands:
+  render        Render an image and save it (default)
+  list          List the available generators
+  help          Print this help
+
+Render options:
+  -w, --width <PIXELS>      Image width in pixels [default: 256]
+  -H, --height <PIXELS>     Image height in pixels [default: 256]. Images are
+                            limited to 67108864 (2^26) pixels in total
+  -o, --output <PATH>       Output file, or '-' for stdout [default: image.png].
+                            Missing directories are created
+  -n, --no-clobber          Fail instead of replacing an existing output file
+      --force               Replace an existing output file (default)
+  -f, --format <FORMAT>     Output format: png, tiff, ppm, ppm-ascii, pgm,
+                            pgm-ascii, pam, hdr, pfm or exr [default: from the
+                            file extension, ppm for stdout]
+      --exr-precision <P>   half or float samples in EXR files [default: half]
+      --exr-compression <C> none or zip [default: zip]
+  -g, --generator <NAME>    Pixel generator to use [default: sky]
+  -p, --param <KEY=VALUE>   Set a generator parameter; may be repeated. See
+                            'output-image list' for each generator's keys
+      --r, --g, --b <EXPR>  Formula for one channel, e.g. --r \"u\" --g \"v\"
+                            --b \"0.25\". Selects the expression generator
+  -t, --time <SECONDS>      Animation time passed to the generator [default: 0]
+  -j, --threads <N>         Render threads, 0 for one per core [default: 0]
+      --progress <STYLE>    Progress on stderr: bar, json (one JSON object per
+                            line) or none [default: bar on a terminal, else none]
+  -q, --quiet               No progress and no completion message
+
+Sampling options:
+  -s, --samples <N>         Samples per pixel [default: 1]
+      --pattern <PATTERN>   grid, jittered, rotated-grid, halton or sobol. The
+                            grid patterns round N to a square [default: grid]
+      --filter <FILTER>     Reconstruction filter: box, tent, gaussian,
+                            mitchell or lanczos [default: box]
+
+Color options:
+      --colormap <MAP>      Recolor the image by luminance with viridis, magma,
+                            turbo, cividis, gray or a CSV table of r,g,b rows
+      --colormap-range <MIN,MAX>
+                            Luminance mapped to the ends of the colormap
+                            [default: 0,1]
+      --lut <FILE>          Apply a .cube lookup table, after any colormap
+
+Quantization options:
+      --clamp <MODE>        clip: clamp each channel, scale: scale bright colors
+                            down keeping their hue [default: clip]
+      --rounding <MODE>     floor: equal-width levels as in the book, nearest:
+                            round to the nearest level [default: floor]
+      --transfer <CURVE>    Transfer function applied before quantizing: none,
+                            srgb, gamma:<N> with N from 0.1 to 10 or linear
+                            (gamma:1). Recorded in PNG files unless none
+                            [default: srgb with color stops, a colormap or a
+                            scene, else none]
+      --bits <BITS>         Significant bits per channel, 1-16. PNG and TIFF use
+                            16-bit samples above 8 [default: 8]
+      --dither <MODE>       none, bayer:<2|4|8|16>, blue-noise, floyd-steinberg,
+                            atkinson or jarvis [default: none]
+      --palette <COLORS>    Limit output to mono, cga, gameboy or a comma
+                            separated list of #rrggbb colors
+
+  -h, --help                Print this help
+
+Exit status:
+  0 success, 2 invalid usage, 3 invalid image size, 4 unsupported format,
+  5 encoding error, 6 I/O error, 7 output file exists with --no-clobber,
+  8 malformed input file";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub output: PathBuf,
+    pub overwrite: Overwrite,
+    pub generator: Generator,
+    pub params: Params,
+    pub time: f64,
+    pub threads: Option<usize>,
+    /// `None` picks a style depending on whether stderr is a terminal.
+    pub progress: Option<Style>,
+    pub quiet: bool,
+    pub sampling: Sampling,
+    pub format: Option<OutputFormat>,
+    pub exr: exr::Options,
+    /// A colormap name or CSV file, applied to the rendered luminance.
+    pub colormap: Option<String>,
+    pub colormap_range: (f64, f64),
+    /// A `.cube` file applied after the colormap.
+    pub lut: Option<PathBuf>,
+    pub quantizer: Quantizer,
+}
+
+impl RenderOptions {
+    pub fn progress_style(&self) -> Style {
+        match self.progress {
+            _ if self.quiet => Style::None,
+            Some(style) => style,
+            None if io::stderr().is_terminal() => Style::Bar,
+            None => Style::None,
+        }
+    }
+
+    /// The explicitly requested format, or else the one implied by the output path.
+    /// Streaming to stdout defaults to binary PPM.
+    pub fn output_format(&self) -> Option<OutputFormat> {
+        let format = if self.output.as_os_str() == "-" {
+            Some(
+                self.format
+                    .unwrap_or(OutputFormat::Netpbm(netpbm::Format::Ppm)),
+            )
+        } else {
+            self.format
+                .or_else(|| OutputFormat::from_path(&self.output))
+        };
+        match format {
+            Some(OutputFormat::OpenExr(_)) => Some(OutputFormat::OpenExr(self.exr)),
+            format => format,
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            output: PathBuf::from(DEFAULT_OUTPUT),
+            overwrite: Overwrite::Always,
+            generator: Generator::Sky,
+            params: Params::new(),
+            time: 0.0,
+            threads: None,
+            progress: None,
+            quiet: false,
+            sampling: Sampling::default(),
+            format: None,
+            exr: exr::Options::default(),
+            colormap: None,
+            colormap_range: (0.0, 1.0),
+            lut: None,
+            quantizer: Quantizer::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Render(Box<RenderOptions>),
+    List,
+    Help,
+}
+
+/// Parses the command line arguments, not including the program name.
+pub fn parse<I>(args: I) -> Result<Command, Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        Some("render") => {
+            args.next();
+            "render"
+        }
+        Some("list") => {
+            args.next();
+            "list"
+        }
+        Some("help") => {
+            args.next();
+            "help"
+        }
+        Some(other) if !other.starts_with('-') => {
+            return Err(Error::Usage(format!("unknown command '{}'", other)))
+        }
+        _ => "render",
+    };
+
+    let mut options = RenderOptions::default();
+    // Resolved once the generator is known.
+    let mut transfer = None;
+
+    while let Some(arg) = args.next() {
+        // Accept both "--width 512" and "--width=512".
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+
+        if flag == "-h" || flag == "--help" {
+            return Ok(Command::Help);
+        }
+        if command != "render" {
+            return Err(Error::Usage(format!(
+                "unexpected argument '{}' for '{}'",
+                arg, command
+            )));
+        }
+
+        // Switches without a value. The last of --no-clobber and --force wins.
+        match flag.as_str() {
+            "-q" | "--quiet" => {
+                options.quiet = true;
+                continue;
+            }
+            "-n" | "--no-clobber" => {
+                options.overwrite = Overwrite::Never;
+                continue;
+            }
+            "--force" => {
+                options.overwrite = Overwrite::Always;
+                continue;
+            }
+            _ => (),
+        }
+
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| Error::Usage(format!("missing value for '{}'", flag)))
+        };
+
+        match flag.as_str() {
+            "-w" | "--width" => options.width = parse_dimension(&flag, &value()?)?,
+            "-H" | "--height" => options.height = parse_dimension(&flag, &value()?)?,
+            "-o" | "--output" => options.output = PathBuf::from(value()?),
+            "-g" | "--generator" => {
+                options.generator = value()?
+                    .parse()
+                    .map_err(|e| Error::Usage(format!("{} (see 'output-image list')", e)))?
+            }
+            "-p" | "--param" => options.params.insert_pair(&value()?)?,
+            "--r" | "--g" | "--b" => {
+                options.generator = Generator::Expression;
+                options.params.set(&flag[2..], value()?);
+            }
+            "-t" | "--time" => options.time = parse_number(&flag, &value()?)?,
+            "-j" | "--threads" => options.threads = parse_threads(&flag, &value()?)?,
+            "-s" | "--samples" => options.sampling.samples = parse_samples(&flag, &value()?)?,
+            "--pattern" => options.sampling.pattern = value()?.parse().map_err(Error::Usage)?,
+            "--filter" => options.sampling.filter = value()?.parse().map_err(Error::Usage)?,
+            "--progress" => options.progress = Some(value()?.parse().map_err(Error::Usage)?),
+            "-f" | "--format" => options.format = Some(value()?.parse().map_err(Error::Usage)?),
+            "--exr-precision" => options.exr.precision = value()?.parse().map_err(Error::Usage)?,
+            "--exr-compression" => {
+                options.exr.compression = value()?.parse().map_err(Error::Usage)?
+            }
+            "--colormap" => options.colormap = Some(value()?),
+            "--colormap-range" => options.colormap_range = parse_range(&flag, &value()?)?,
+            "--lut" => options.lut = Some(PathBuf::from(value()?)),
+            "--clamp" => options.quantizer.clamp = value()?.parse().map_err(Error::Usage)?,
+            "--rounding" => options.quantizer.rounding = value()?.parse().map_err(Error::Usage)?,
+            "--transfer" => transfer = Some(value()?.parse().map_err(Error::Usage)?),
+            "--dither" => options.quantizer.dither = value()?.parse().map_err(Error::Usage)?,
+            "--palette" => {
+                options.quantizer.palette = Some(value()?.parse().map_err(Error::Usage)?)
+            }
+            "--bits" => options.quantizer.bits = parse_bits(&flag, &value()?)?,
+            _ => return Err(Error::Usage(format!("unknown option '{}'", arg))),
+        }
+    }
+
+    match command {
+        "list" => Ok(Command::List),
+        "help" => Ok(Command::Help),
+        _ => {
+            Canvas::check_size(options.width, options.height)?;
+            // Colormaps are sRGB as well, whatever drew the image.
+            options.quantizer.transfer = match transfer {
+                Some(transfer) => transfer,
+                None if options.colormap.is_some() => Transfer::Srgb,
+                None => options.generator.default_transfer(),
+            };
+            Ok(Command::Render(Box::new(options)))
+        }
+    }
+}
+
+fn parse_dimension(flag: &str, value: &str) -> Result<u32, Error> {
+    value.parse::<u32>().map_err(|_| {
+        Error::Usage(format!(
+            "invalid value '{}' for '{}': expected a positive integer",
+            value, flag
+        ))
+    })
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<f64, Error> {
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(Error::Usage(format!(
+            "invalid value '{}' for '{}': expected a number",
+            value, flag
+        ))),
+    }
+}
+
+fn parse_range(flag: &str, value: &str) -> Result<(f64, f64), Error> {
+    match params::parse_pair(value) {
+        Ok((min, max)) if min != max => Ok((min, max)),
+        _ => Err(Error::Usage(format!(
+            "invalid value '{}' for '{}': expected two different numbers as min,max",
+            value, flag
+        ))),
+    }
+}
+
+fn parse_threads(flag: &str, value: &str) -> Result<Option<usize>, Error> {
+    match value.parse::<usize>() {
+        Ok(0) => Ok(None),
+        Ok(n) => Ok(Some(n)),
+        Err(_) => Err(Error::Usage(format!(
+            "invalid value '{}' for '{}': expected a thread count",
+            value, flag
+        ))),
+    }
+}
+
+fn parse_samples(flag: &str, value: &str) -> Result<u32, Error> {
+    match value.parse::<u32>() {
+        Ok(n) if (1..=65536).contains(&n) => Ok(n),
+        _ => Err(Error::Usage(format!(
+            "invalid value '{}' for '{}': expected a sample count from 1 to 65536",
+            value, flag
+        ))),
+    }
+}
+
+fn parse_bits(flag: &str, value: &str) -> Result<u8, Error> {
+    match value.parse::<u8>() {
+        Ok(bits) if (1..=Quantizer::MAX_BITS).contains(&bits) => Ok(bits),
+        _ => Err(Error::Usage(format!(
+            "invalid value '{}' for '{}': expected a bit depth from 1 to {}",
+            value,
+            flag,
+            Quantizer::MAX_BITS
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn usage_error(list: &[&str]) -> String {
+        match parse(args(list)) {
+            Err(Error::Usage(message)) => message,
+            other => panic!("expected a usage error for {:?}, got {:?}", list, other),
+        }
+    }
+
+    #[test]
+    fn values_can_follow_an_equals_sign_or_a_space() {
+        let joined = parse(args(&["--width=320", "--height=200"])).unwrap();
+        let separate = parse(args(&["--width", "320", "-H", "200"])).unwrap();
+        assert_eq!(joined, separate);
+        match joined {
+            Command::Render(options) => assert_eq!((options.width, options.height), (320, 200)),
+            other => panic!("expected a render command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_arguments_are_usage_errors() {
+        assert_eq!(usage_error(&["--width"]), "missing value for '--width'");
+        assert_eq!(usage_error(&["--colour", "red"]), "unknown option '--colour'");
+        assert!(usage_error(&["--width", "wide"]).contains("expected a positive integer"));
+        assert!(usage_error(&["--height=-4"]).contains("expected a positive integer"));
+    }
+
+    #[test]
+    fn color_stops_and_colormaps_default_to_srgb_output() {
+        let transfer = |list: &[&str]| match parse(args(list)).unwrap() {
+            Command::Render(options) => options.quantizer.transfer,
+            other => panic!("expected a render command, got {:?}", other),
+        };
+        assert_eq!(transfer(&[]), Transfer::PassThrough);
+        assert_eq!(transfer(&["-g", "radial-gradient"]), Transfer::Srgb);
+        assert_eq!(transfer(&["-g", "worley"]), Transfer::Srgb);
+        assert_eq!(transfer(&["-g", "scene"]), Transfer::Srgb);
+        assert_eq!(transfer(&["-g", "sky", "--colormap", "magma"]), Transfer::Srgb);
+        assert_eq!(transfer(&["--transfer", "none", "-g", "radial-gradient"]), Transfer::PassThrough);
+        assert_eq!(transfer(&["-g", "sky", "--transfer=gamma:2"]), Transfer::Gamma(2.0));
+    }
+
+    #[test]
+    fn empty_or_oversized_images_are_rejected() {
+        assert!(matches!(parse(args(&["--width", "0"])), Err(Error::InvalidDimensions { .. })));
+        let side = (1u64 << 13).to_string();
+        assert!(parse(args(&["-w", &side, "-H", &side])).is_ok());
+        assert!(matches!(
+            parse(args(&["-w", &side, "-H", "8193"])),
+            Err(Error::InvalidDimensions { .. })
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use image::Rgb;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub};
+
+/// A linear RGB color. Channels are nominally in `0.0..=1.0` but may go outside that
+/// range while rendering; clamping happens only when the image is quantized.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f64, g: f64, b: f64) -> Color {
+        Color { r, g, b }
+    }
+
+    pub const fn gray(v: f64) -> Color {
+        Color::new(v, v, v)
+    }
+
+    /// The largest of the three channels.
+    pub fn max_channel(self) -> f64 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    /// Relative luminance with the Rec. 709 weights. Grays map to their own value.
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Linear interpolation: `self` at `t = 0` and `other` at `t = 1`.
+    pub fn lerp(self, other: Color, t: f64) -> Color {
+        self + (other - self) * t
+    }
+
+    /// The smaller of each pair of channels.
+    pub fn min(self, other: Color) -> Color {
+        Color::new(self.r.min(other.r), self.g.min(other.g), self.b.min(other.b))
+    }
+
+    /// The larger of each pair of channels.
+    pub fn max(self, other: Color) -> Color {
+        Color::new(self.r.max(other.r), self.g.max(other.g), self.b.max(other.b))
+    }
+
+    /// The 8-bit pixel for this color as written in the book: each channel clamped
+    /// to `0.0..=1.0` and mapped with `(255.999 * v) as u8`, with no transfer curve.
+    pub fn to_rgb8(self) -> Rgb<u8> {
+        let channel = |v: f64| (255.999 * v.clamp(0.0, 1.0)) as u8;
+        Rgb([channel(self.r), channel(self.g), channel(self.b)])
+    }
+}
+
+impl From<Color> for Rgb<u8> {
+    fn from(c: Color) -> Rgb<u8> {
+        c.to_rgb8()
+    }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        Color::new(self.r - other.r, self.g - other.g, self.b - other.b)
+    }
+}
+
+impl Neg for Color {
+    type Output = Color;
+
+    fn neg(self) -> Color {
+        Color::new(-self.r, -self.g, -self.b)
+    }
+}
+
+/// Channel-wise product, as when light is filtered by a colored surface.
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color::new(self.r * other.r, self.g * other.g, self.b * other.b)
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, t: f64) -> Color {
+        Color::new(self.r * t, self.g * t, self.b * t)
+    }
+}
+
+impl Mul<Color> for f64 {
+    type Output = Color;
+
+    fn mul(self, c: Color) -> Color {
+        c * self
+    }
+}
+
+impl MulAssign<f64> for Color {
+    fn mul_assign(&mut self, t: f64) {
+        *self = *self * t;
+    }
+}
+
+impl Div<f64> for Color {
+    type Output = Color;
+
+    fn div(self, t: f64) -> Color {
+        self * (1.0 / t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_is_channel_wise() {
+        let (a, b) = (Color::new(0.5, 0.25, 1.0), Color::new(0.25, 0.5, 0.5));
+        assert_eq!(a + b, Color::new(0.75, 0.75, 1.5));
+        assert_eq!(a - b, Color::new(0.25, -0.25, 0.5));
+        assert_eq!(-a, Color::new(-0.5, -0.25, -1.0));
+        assert_eq!(a * b, Color::new(0.125, 0.125, 0.5));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(a / 2.0, Color::new(0.25, 0.125, 0.5));
+        let mut c = a;
+        c *= 4.0;
+        c += b;
+        assert_eq!(c, Color::new(2.25, 1.5, 4.5));
+    }
+
+    #[test]
+    fn lerp_min_and_max() {
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.25), Color::gray(0.25));
+        let (a, b) = (Color::new(0.1, 0.9, 0.5), Color::new(0.2, 0.3, 0.5));
+        assert_eq!(a.min(b), Color::new(0.1, 0.3, 0.5));
+        assert_eq!(a.max(b), Color::new(0.2, 0.9, 0.5));
+    }
+
+    #[test]
+    fn converts_to_rgb8_like_the_book() {
+        // The pixel at (128, 64) of the original 256x256 gradient.
+        let c = Color::new(128.0 / 255.0, 64.0 / 255.0, 0.25);
+        assert_eq!(Rgb::from(c), Rgb([128, 64, 63]));
+        assert_eq!(Color::WHITE.to_rgb8(), Rgb([255, 255, 255]));
+        assert_eq!(Color::new(-1.0, 2.0, 0.999).to_rgb8(), Rgb([0, 255, 255]));
+    }
+}
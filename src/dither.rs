@@ -0,0 +1,395 @@
+//! Dithering and palettes for the quantization stage.
+
+use crate::color::Color;
+use crate::rng::Rng;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// How quantization error is hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dither {
+    #[default]
+    None,
+    /// Ordered dithering with an `n` x `n` Bayer matrix, `n` in 2, 4, 8 or 16.
+    Bayer(u32),
+    /// Ordered dithering with a 64 x 64 void-and-cluster blue noise mask.
+    BlueNoise,
+    FloydSteinberg,
+    Atkinson,
+    /// Jarvis, Judice and Ninke.
+    Jarvis,
+}
+
+/// A weight for the pixel at `(dx, dy)` from the current one, before division by
+/// the kernel's divisor.
+type Tap = (i32, i32, f64);
+
+const FLOYD_STEINBERG: (&[Tap], f64) = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+
+// Atkinson deliberately diffuses only 6/8 of the error, which keeps highlights and
+// shadows clean.
+const ATKINSON: (&[Tap], f64) = (
+    &[(1, 0, 1.0), (2, 0, 1.0), (-1, 1, 1.0), (0, 1, 1.0), (1, 1, 1.0), (0, 2, 1.0)],
+    8.0,
+);
+
+const JARVIS: (&[Tap], f64) = (
+    &[
+        (1, 0, 7.0),
+        (2, 0, 5.0),
+        (-2, 1, 3.0),
+        (-1, 1, 5.0),
+        (0, 1, 7.0),
+        (1, 1, 5.0),
+        (2, 1, 3.0),
+        (-2, 2, 1.0),
+        (-1, 2, 3.0),
+        (0, 2, 5.0),
+        (1, 2, 3.0),
+        (2, 2, 1.0),
+    ],
+    48.0,
+);
+
+impl Dither {
+    /// The error diffusion kernel and its divisor, if this is an error diffusion method.
+    pub(crate) fn kernel(self) -> Option<(&'static [Tap], f64)> {
+        match self {
+            Dither::FloydSteinberg => Some(FLOYD_STEINBERG),
+            Dither::Atkinson => Some(ATKINSON),
+            Dither::Jarvis => Some(JARVIS),
+            _ => None,
+        }
+    }
+
+    /// The ordered dithering threshold at pixel `(x, y)`, in `0.0..1.0`, if this is an
+    /// ordered method.
+    pub(crate) fn threshold(self, x: u32, y: u32) -> Option<f64> {
+        match self {
+            Dither::Bayer(n) => Some((bayer(n, x % n, y % n) as f64 + 0.5) / (n * n) as f64),
+            Dither::BlueNoise => {
+                let mask = blue_noise();
+                let i = (y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE;
+                Some(mask[i as usize])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The rank of `(x, y)` in an `n` x `n` Bayer matrix, built by recursively tiling
+/// the 2 x 2 pattern `[0 2; 3 1]`.
+fn bayer(n: u32, x: u32, y: u32) -> u32 {
+    if n == 1 {
+        return 0;
+    }
+    let half = n / 2;
+    let quadrant = match (x / half, y / half) {
+        (0, 0) => 0,
+        (1, 0) => 2,
+        (0, _) => 3,
+        _ => 1,
+    };
+    4 * bayer(half, x % half, y % half) + quadrant
+}
+
+const BLUE_NOISE_SIZE: u32 = 64;
+
+/// Thresholds from a blue noise mask generated once with Ulichney's
+/// void-and-cluster method.
+fn blue_noise() -> &'static [f64] {
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    MASK.get_or_init(|| void_and_cluster(BLUE_NOISE_SIZE as usize, 1.5, 0x5eed))
+}
+
+fn void_and_cluster(size: usize, sigma: f64, seed: u64) -> Vec<f64> {
+    let n = size * size;
+
+    // Gaussian weight for every toroidal offset.
+    let mut kernel = vec![0.0; n];
+    for dy in 0..size {
+        for dx in 0..size {
+            let wx = dx.min(size - dx) as f64;
+            let wy = dy.min(size - dy) as f64;
+            kernel[dy * size + dx] = (-(wx * wx + wy * wy) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+
+    let mut energy = vec![0.0; n];
+    let mut ones = vec![false; n];
+    let toggle = |ones: &mut Vec<bool>, energy: &mut Vec<f64>, i: usize| {
+        let sign = if ones[i] { -1.0 } else { 1.0 };
+        ones[i] = !ones[i];
+        let (ix, iy) = (i % size, i / size);
+        for (j, e) in energy.iter_mut().enumerate() {
+            let dx = (j % size + size - ix) % size;
+            let dy = (j / size + size - iy) % size;
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+    let tightest_cluster = |ones: &[bool], energy: &[f64]| {
+        (0..n).filter(|&i| ones[i]).max_by(|&a, &b| energy[a].partial_cmp(&energy[b]).unwrap())
+    };
+    let largest_void = |ones: &[bool], energy: &[f64]| {
+        (0..n).filter(|&i| !ones[i]).min_by(|&a, &b| energy[a].partial_cmp(&energy[b]).unwrap())
+    };
+
+    // Random initial pattern with about a tenth of the pixels set.
+    let mut rng = Rng::new(seed);
+    let initial = n / 10;
+    let mut count = 0;
+    while count < initial {
+        let i = rng.below(n);
+        if !ones[i] {
+            toggle(&mut ones, &mut energy, i);
+            count += 1;
+        }
+    }
+
+    // Spread the points out by moving the tightest cluster into the largest void
+    // until that would move a point straight back.
+    loop {
+        let cluster = tightest_cluster(&ones, &energy).unwrap();
+        toggle(&mut ones, &mut energy, cluster);
+        let void = largest_void(&ones, &energy).unwrap();
+        toggle(&mut ones, &mut energy, void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0usize; n];
+
+    // Rank the initial points by removing clusters one at a time.
+    let (mut removed_ones, mut removed_energy) = (ones.clone(), energy.clone());
+    for r in (0..initial).rev() {
+        let cluster = tightest_cluster(&removed_ones, &removed_energy).unwrap();
+        toggle(&mut removed_ones, &mut removed_energy, cluster);
+        rank[cluster] = r;
+    }
+
+    // Rank the rest by filling voids. With a linear kernel, the tightest cluster of
+    // zeros in the second half is also the largest void of ones, so one loop covers
+    // both halves.
+    for r in initial..n {
+        let void = largest_void(&ones, &energy).unwrap();
+        toggle(&mut ones, &mut energy, void);
+        rank[void] = r;
+    }
+
+    rank.into_iter().map(|r| (r as f64 + 0.5) / n as f64).collect()
+}
+
+/// A fixed set of output colors, in display-encoded values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colors: Vec<Color>,
+    spread: f64,
+}
+
+impl Palette {
+    /// Creates a palette from display-encoded colors. Panics if `colors` is empty.
+    pub fn new(colors: Vec<Color>) -> Palette {
+        assert!(!colors.is_empty(), "a palette needs at least one color");
+
+        // Ordered dithering nudges colors by up to the typical gap between palette
+        // entries, measured here as the mean distance to the nearest other entry.
+        let mut total = 0.0;
+        for (i, a) in colors.iter().enumerate() {
+            let nearest = colors
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, b)| distance_squared(*a, *b).sqrt())
+                .fold(f64::INFINITY, f64::min);
+            if nearest.is_finite() {
+                total += nearest;
+            }
+        }
+        let spread = total / colors.len() as f64 / 3f64.sqrt();
+
+        Palette { colors, spread }
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// How far ordered dithering may push a color before picking an entry.
+    pub(crate) fn spread(&self) -> f64 {
+        self.spread
+    }
+
+    /// The entry closest to `color` in Euclidean distance.
+    pub fn nearest(&self, color: Color) -> Color {
+        *self
+            .colors
+            .iter()
+            .min_by(|a, b| {
+                distance_squared(**a, color)
+                    .partial_cmp(&distance_squared(**b, color))
+                    .unwrap()
+            })
+            .unwrap()
+    }
+}
+
+fn distance_squared(a: Color, b: Color) -> f64 {
+    let (dr, dg, db) = (a.r - b.r, a.g - b.g, a.b - b.b);
+    dr * dr + dg * dg + db * db
+}
+
+/// Parses a `#rrggbb` or `#rgb` color into display-encoded values.
+pub fn parse_hex(s: &str) -> Result<Color, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let digits: Vec<u32> = hex
+        .chars()
+        .map(|c| c.to_digit(16))
+        .collect::<Option<_>>()
+        .ok_or_else(|| format!("invalid hex color '{}'", s))?;
+    let (r, g, b) = match digits.len() {
+        3 => (digits[0] * 17, digits[1] * 17, digits[2] * 17),
+        6 => (
+            digits[0] * 16 + digits[1],
+            digits[2] * 16 + digits[3],
+            digits[4] * 16 + digits[5],
+        ),
+        _ => return Err(format!("invalid hex color '{}'", s)),
+    };
+    Ok(Color::new(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0))
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Accepts a built-in name (`mono`, `cga`, `gameboy`) or a comma separated list
+    /// of hex colors.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = match s {
+            "mono" => "#000000,#ffffff",
+            "cga" => "#000000,#55ffff,#ff55ff,#ffffff",
+            "gameboy" => "#0f380f,#306230,#8bac0f,#9bbc0f",
+            _ => s,
+        };
+        let colors = hex.split(',').map(|c| parse_hex(c.trim())).collect::<Result<Vec<_>, _>>()?;
+        Ok(Palette::new(colors))
+    }
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Dither::None),
+            "blue-noise" => Ok(Dither::BlueNoise),
+            "floyd-steinberg" => Ok(Dither::FloydSteinberg),
+            "atkinson" => Ok(Dither::Atkinson),
+            "jarvis" => Ok(Dither::Jarvis),
+            _ => match s.strip_prefix("bayer:").map(str::parse::<u32>) {
+                Some(Ok(n)) if [2, 4, 8, 16].contains(&n) => Ok(Dither::Bayer(n)),
+                _ => Err(format!(
+                    "unknown dither mode '{}' (expected none, bayer:<2|4|8|16>, blue-noise, \
+                     floyd-steinberg, atkinson or jarvis)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Quantizer;
+
+    /// The thresholds of one tile of an ordered dither, sorted.
+    fn thresholds(dither: Dither, size: u32) -> Vec<f64> {
+        let mut t: Vec<f64> = (0..size * size).map(|i| dither.threshold(i % size, i / size).unwrap()).collect();
+        t.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        t
+    }
+
+    #[test]
+    fn ordered_thresholds_cover_the_unit_interval_evenly() {
+        for &(dither, size) in &[
+            (Dither::Bayer(2), 2),
+            (Dither::Bayer(4), 4),
+            (Dither::Bayer(8), 8),
+            (Dither::Bayer(16), 16),
+            (Dither::BlueNoise, BLUE_NOISE_SIZE),
+        ] {
+            // Every rank appears exactly once, centered in its share of 0..1.
+            let n = (size * size) as f64;
+            for (rank, &t) in thresholds(dither, size).iter().enumerate() {
+                assert!(t > 0.0 && t < 1.0);
+                assert!((t - (rank as f64 + 0.5) / n).abs() < 1e-12, "{:?}", dither);
+            }
+        }
+        // Matrices tile the plane.
+        assert_eq!(Dither::Bayer(4).threshold(1, 2), Dither::Bayer(4).threshold(5, 6));
+        assert_eq!(Dither::None.threshold(0, 0), None);
+    }
+
+    #[test]
+    fn error_diffusion_preserves_the_average_of_a_flat_field() {
+        let mean = |dither: Dither, v: f64| {
+            let (width, height) = (64, 64);
+            let quantizer = Quantizer {
+                bits: 1,
+                dither,
+                ..Quantizer::default()
+            };
+            let pixels = vec![Color::gray(v); width * height];
+            let levels = quantizer.quantize(width as u32, height as u32, &pixels);
+            assert!(levels.iter().all(|l| l[0] == l[1] && l[1] == l[2]));
+            levels.iter().map(|l| l[0] as f64).sum::<f64>() / levels.len() as f64
+        };
+        // Only error pushed off the edges of the image is lost, and Jarvis reaches
+        // two pixels past them.
+        for &(dither, tolerance) in &[(Dither::FloydSteinberg, 0.01), (Dither::Jarvis, 0.015)] {
+            for &v in &[0.1, 0.3, 0.5, 0.8] {
+                let mean = mean(dither, v);
+                assert!((mean - v).abs() < tolerance, "{:?} {}: {}", dither, v, mean);
+            }
+        }
+        // Atkinson drops a quarter of the error, so only mid gray keeps its average.
+        // Darker fields come out darker, down to black, and lighter ones lighter.
+        assert!((mean(Dither::Atkinson, 0.5) - 0.5).abs() < 0.01);
+        assert_eq!(mean(Dither::Atkinson, 0.1), 0.0);
+        assert!(mean(Dither::Atkinson, 0.3) < 0.29);
+        assert!(mean(Dither::Atkinson, 0.8) > 0.81);
+    }
+
+    #[test]
+    fn palettes_limit_every_mode_to_their_colors() {
+        let palette: Palette = "gameboy".parse().unwrap();
+        let entries: Vec<[u32; 3]> = palette
+            .colors()
+            .iter()
+            .map(|c| [c.r, c.g, c.b].map(|v| (v * 255.0).round() as u32))
+            .collect();
+        let (width, height) = (32, 16);
+        let pixels: Vec<Color> = (0..width * height)
+            .map(|i| Color::new((i % width) as f64 / 31.0, (i / width) as f64 / 15.0, 0.5))
+            .collect();
+        for &dither in &[
+            Dither::None,
+            Dither::Bayer(4),
+            Dither::BlueNoise,
+            Dither::FloydSteinberg,
+            Dither::Atkinson,
+            Dither::Jarvis,
+        ] {
+            let quantizer = Quantizer {
+                dither,
+                palette: Some(palette.clone()),
+                ..Quantizer::default()
+            };
+            let levels = quantizer.quantize(width as u32, height as u32, &pixels);
+            assert!(levels.iter().all(|l| entries.contains(l)), "{:?}", dither);
+            // A gradient this wide reaches more than one of them.
+            assert!(levels.iter().any(|l| l != &levels[0]), "{:?}", dither);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::netpbm::DecodeError;
+use image::error::ImageError;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Everything that can go wrong producing an image.
+#[derive(Debug)]
+pub enum Error {
+    /// The command line or another caller-supplied setting was malformed.
+    Usage(String),
+    /// The requested image size is zero or too large.
+    InvalidDimensions { width: u32, height: u32, reason: String },
+    /// The output format is unknown or cannot hold this image.
+    UnsupportedFormat(String),
+    /// The image could not be encoded.
+    Encoding(String),
+    /// Reading or writing a file or stream failed, or the system could not provide
+    /// a resource such as render threads.
+    Io(io::Error),
+    /// The output file exists and overwriting it was not allowed.
+    OutputExists(PathBuf),
+    /// An input file such as a lookup table is malformed. `line` is 1-based, or 0
+    /// if the problem is not on a particular line. `path` is empty for input that
+    /// did not come from a named file.
+    Parse { path: PathBuf, line: usize, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The process exit status for this class of error:
+    ///
+    /// | Status | Meaning                    |
+    /// |--------|----------------------------|
+    /// | 2      | Invalid usage              |
+    /// | 3      | Invalid image dimensions   |
+    /// | 4      | Unsupported format         |
+    /// | 5      | Encoding error             |
+    /// | 6      | I/O error                  |
+    /// | 7      | Output file already exists |
+    /// | 8      | Malformed input file       |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::InvalidDimensions { .. } => 3,
+            Error::UnsupportedFormat(_) => 4,
+            Error::Encoding(_) => 5,
+            Error::Io(_) => 6,
+            Error::OutputExists(_) => 7,
+            Error::Parse { .. } => 8,
+        }
+    }
+
+    /// Names `path` in an I/O error, since `io::Error` messages do not say which
+    /// file they are about. Other errors are returned unchanged.
+    pub fn with_path(self, path: &Path) -> Error {
+        match self {
+            Error::Io(e) => Error::Io(io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => f.write_str(msg),
+            Error::InvalidDimensions { width, height, reason } => {
+                write!(f, "invalid image size {}x{}: {}", width, height, reason)
+            }
+            Error::UnsupportedFormat(msg) => write!(f, "unsupported format: {}", msg),
+            Error::Encoding(msg) => write!(f, "encoding failed: {}", msg),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::OutputExists(path) => write!(f, "{} already exists", path.display()),
+            Error::Parse { path, message, .. } if path.as_os_str().is_empty() => f.write_str(message),
+            Error::Parse { path, line: 0, message } => write!(f, "{}: {}", path.display(), message),
+            Error::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(e: ImageError) -> Self {
+        match e {
+            ImageError::IoError(e) => Error::Io(e),
+            ImageError::Unsupported(e) => Error::UnsupportedFormat(e.to_string()),
+            e => Error::Encoding(e.to_string()),
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::Io(e) => Error::Io(e),
+            DecodeError::Unsupported(_) => Error::UnsupportedFormat(e.to_string()),
+            DecodeError::Invalid(_) => Error::Parse {
+                path: PathBuf::new(),
+                line: 0,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_error_has_its_own_exit_status() {
+        let errors = [
+            (Error::Usage("bad flag".into()), 2),
+            (
+                Error::InvalidDimensions {
+                    width: 0,
+                    height: 1,
+                    reason: "empty".into(),
+                },
+                3,
+            ),
+            (Error::UnsupportedFormat("xyz".into()), 4),
+            (Error::Encoding("broken".into()), 5),
+            (Error::Io(io::Error::from(io::ErrorKind::PermissionDenied)), 6),
+            (Error::OutputExists(PathBuf::from("image.png")), 7),
+            (
+                Error::Parse {
+                    path: PathBuf::from("table.cube"),
+                    line: 3,
+                    message: "bad row".into(),
+                },
+                8,
+            ),
+        ];
+        for (error, status) in &errors {
+            assert_eq!(error.exit_code(), *status, "{}", error);
+        }
+
+        // Netpbm decode errors land in the matching class, malformed input included.
+        let invalid = Error::from(DecodeError::Invalid("maxval 0 is out of range".into()));
+        assert_eq!(invalid.exit_code(), 8);
+        assert_eq!(invalid.to_string(), "invalid Netpbm image: maxval 0 is out of range");
+        assert_eq!(Error::from(DecodeError::Unsupported("tuple depth 5".into())).exit_code(), 4);
+        assert_eq!(Error::from(DecodeError::Io(io::ErrorKind::UnexpectedEof.into())).exit_code(), 6);
+    }
+}
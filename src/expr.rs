@@ -0,0 +1,765 @@
+//! A small expression language for per-pixel formulas given at run time.
+//!
+//! Expressions use the usual arithmetic operators (`+ - * / %`, `^` for powers),
+//! comparisons that give 1 or 0, the constants `pi`, `tau` and `e`, the variables
+//! below and functions such as `sin`, `mix`, `clamp`, `smoothstep` and `noise`.
+//!
+//! | Variable | Meaning |
+//! |----------|---------|
+//! | `x`, `y` | Pixel position, from the top left |
+//! | `u`, `v` | Position normalized to `0..1` |
+//! | `w`, `h` | Image width and height in pixels |
+//! | `t`      | Animation time in seconds |
+//!
+//! Parsed expressions are folded and compiled into a list of stack operations, so
+//! evaluating one per pixel does no allocation and no name lookups.
+
+use crate::color::Color;
+use crate::noise::{Noise, OpenSimplex};
+use crate::shader::{ShadeContext, Shader};
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in an expression, at a character position counted from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    X,
+    Y,
+    U,
+    V,
+    W,
+    H,
+    T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Binary {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Binary {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        let truth = |c: bool| if c { 1.0 } else { 0.0 };
+        match self {
+            Binary::Add => a + b,
+            Binary::Sub => a - b,
+            Binary::Mul => a * b,
+            Binary::Div => a / b,
+            Binary::Rem => a.rem_euclid(b),
+            Binary::Pow => a.powf(b),
+            Binary::Less => truth(a < b),
+            Binary::LessEqual => truth(a <= b),
+            Binary::Greater => truth(a > b),
+            Binary::GreaterEqual => truth(a >= b),
+            Binary::Equal => truth(a == b),
+            Binary::NotEqual => truth(a != b),
+        }
+    }
+}
+
+/// A built-in function. Each takes a fixed number of arguments, except `noise`
+/// which is overloaded by its dimension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Neg,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Exp,
+    Ln,
+    Log2,
+    Log10,
+    Pow,
+    Hypot,
+    Min,
+    Max,
+    Mod,
+    Step,
+    Mix,
+    Clamp,
+    Smoothstep,
+    Noise2,
+    Noise3,
+}
+
+impl Function {
+    fn lookup(name: &str, arity: usize) -> Option<Function> {
+        let f = match (name, arity) {
+            ("sin", 1) => Function::Sin,
+            ("cos", 1) => Function::Cos,
+            ("tan", 1) => Function::Tan,
+            ("asin", 1) => Function::Asin,
+            ("acos", 1) => Function::Acos,
+            ("atan", 1) => Function::Atan,
+            ("atan2", 2) => Function::Atan2,
+            ("sqrt", 1) => Function::Sqrt,
+            ("abs", 1) => Function::Abs,
+            ("sign", 1) => Function::Sign,
+            ("floor", 1) => Function::Floor,
+            ("ceil", 1) => Function::Ceil,
+            ("round", 1) => Function::Round,
+            ("fract", 1) => Function::Fract,
+            ("exp", 1) => Function::Exp,
+            ("ln", 1) => Function::Ln,
+            ("log2", 1) => Function::Log2,
+            ("log10", 1) => Function::Log10,
+            ("pow", 2) => Function::Pow,
+            ("hypot", 2) => Function::Hypot,
+            ("min", 2) => Function::Min,
+            ("max", 2) => Function::Max,
+            ("mod", 2) => Function::Mod,
+            ("step", 2) => Function::Step,
+            ("mix", 3) => Function::Mix,
+            ("clamp", 3) => Function::Clamp,
+            ("smoothstep", 3) => Function::Smoothstep,
+            ("noise", 2) => Function::Noise2,
+            ("noise", 3) => Function::Noise3,
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    /// The number of arguments each function accepts, for error messages.
+    fn arity(name: &str) -> Option<&'static str> {
+        match name {
+            "sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "sqrt" | "abs" | "sign" | "floor" | "ceil"
+            | "round" | "fract" | "exp" | "ln" | "log2" | "log10" => Some("1 argument"),
+            "atan2" | "pow" | "hypot" | "min" | "max" | "mod" | "step" => Some("2 arguments"),
+            "mix" | "clamp" | "smoothstep" => Some("3 arguments"),
+            "noise" => Some("2 or 3 arguments"),
+            _ => None,
+        }
+    }
+
+    fn arguments(self) -> usize {
+        match self {
+            Function::Atan2
+            | Function::Pow
+            | Function::Hypot
+            | Function::Min
+            | Function::Max
+            | Function::Mod
+            | Function::Step
+            | Function::Noise2 => 2,
+            Function::Mix | Function::Clamp | Function::Smoothstep | Function::Noise3 => 3,
+            _ => 1,
+        }
+    }
+
+    /// Applies the function to its arguments, the first one first.
+    fn apply(self, a: &[f64]) -> f64 {
+        const NOISE: OpenSimplex = OpenSimplex { seed: 0 };
+        match self {
+            Function::Neg => -a[0],
+            Function::Sin => a[0].sin(),
+            Function::Cos => a[0].cos(),
+            Function::Tan => a[0].tan(),
+            Function::Asin => a[0].asin(),
+            Function::Acos => a[0].acos(),
+            Function::Atan => a[0].atan(),
+            Function::Atan2 => a[0].atan2(a[1]),
+            Function::Sqrt => a[0].sqrt(),
+            Function::Abs => a[0].abs(),
+            Function::Sign => {
+                if a[0] == 0.0 {
+                    0.0
+                } else {
+                    a[0].signum()
+                }
+            }
+            Function::Floor => a[0].floor(),
+            Function::Ceil => a[0].ceil(),
+            Function::Round => a[0].round(),
+            Function::Fract => a[0] - a[0].floor(),
+            Function::Exp => a[0].exp(),
+            Function::Ln => a[0].ln(),
+            Function::Log2 => a[0].log2(),
+            Function::Log10 => a[0].log10(),
+            Function::Pow => a[0].powf(a[1]),
+            Function::Hypot => a[0].hypot(a[1]),
+            Function::Min => a[0].min(a[1]),
+            Function::Max => a[0].max(a[1]),
+            Function::Mod => a[0].rem_euclid(a[1]),
+            // As in GLSL: step(edge, x) and smoothstep(edge0, edge1, x).
+            Function::Step => {
+                if a[1] < a[0] {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Function::Mix => a[0] + (a[1] - a[0]) * a[2],
+            Function::Clamp => a[0].max(a[1]).min(a[2]),
+            Function::Smoothstep => {
+                let t = ((a[2] - a[0]) / (a[1] - a[0])).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            Function::Noise2 => NOISE.noise2([a[0], a[1]]),
+            Function::Noise3 => NOISE.noise3([a[0], a[1], a[2]]),
+        }
+    }
+}
+
+/// The parsed form of an expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Variable(Variable),
+    Binary(Binary, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    /// Replaces every subexpression that does not depend on a variable with its value.
+    fn fold(self) -> Node {
+        match self {
+            Node::Binary(op, a, b) => match (a.fold(), b.fold()) {
+                (Node::Number(a), Node::Number(b)) => Node::Number(op.apply(a, b)),
+                (a, b) => Node::Binary(op, Box::new(a), Box::new(b)),
+            },
+            Node::Call(f, args) => {
+                let args: Vec<Node> = args.into_iter().map(Node::fold).collect();
+                let values: Option<Vec<f64>> = args
+                    .iter()
+                    .map(|a| match a {
+                        Node::Number(v) => Some(*v),
+                        _ => None,
+                    })
+                    .collect();
+                match values {
+                    Some(values) => Node::Number(f.apply(&values)),
+                    None => Node::Call(f, args),
+                }
+            }
+            node => node,
+        }
+    }
+
+    /// Appends the stack operations for this node and returns the stack depth they
+    /// need.
+    fn compile(&self, ops: &mut Vec<Op>) -> usize {
+        match self {
+            Node::Number(v) => {
+                ops.push(Op::Number(*v));
+                1
+            }
+            Node::Variable(v) => {
+                ops.push(Op::Variable(*v));
+                1
+            }
+            Node::Binary(op, a, b) => {
+                let depth = a.compile(ops).max(1 + b.compile(ops));
+                ops.push(Op::Binary(*op));
+                depth
+            }
+            Node::Call(f, args) => {
+                let depth = args.iter().enumerate().map(|(i, a)| i + a.compile(ops)).max().unwrap_or(0);
+                ops.push(Op::Call(*f));
+                depth
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Number(f64),
+    Variable(Variable),
+    Binary(Binary),
+    Call(Function),
+}
+
+/// A compiled expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    ops: Vec<Op>,
+}
+
+impl Expr {
+    /// The deepest stack an expression may need. Only absurdly nested expressions
+    /// come anywhere near it.
+    const MAX_DEPTH: usize = 64;
+
+    pub fn parse(source: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser::new(source)?;
+        let node = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error_at(token.column, format!("unexpected {}", token.kind)));
+        }
+        let mut ops = Vec::new();
+        if node.fold().compile(&mut ops) > Expr::MAX_DEPTH {
+            return Err(ParseError {
+                column: 1,
+                message: "expression is nested too deeply".to_string(),
+            });
+        }
+        Ok(Expr { ops })
+    }
+
+    /// The value of a constant expression, or `None` if it depends on a variable.
+    pub fn constant(&self) -> Option<f64> {
+        match self.ops[..] {
+            [Op::Number(v)] => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the expression for a pixel.
+    pub fn eval(&self, ctx: &ShadeContext) -> f64 {
+        let mut stack = [0.0; Expr::MAX_DEPTH];
+        let mut top = 0;
+        for op in &self.ops {
+            match *op {
+                Op::Number(v) => {
+                    stack[top] = v;
+                    top += 1;
+                }
+                Op::Variable(v) => {
+                    stack[top] = match v {
+                        Variable::X => ctx.x,
+                        Variable::Y => ctx.y,
+                        Variable::U => ctx.u,
+                        Variable::V => ctx.v,
+                        Variable::W => ctx.width as f64,
+                        Variable::H => ctx.height as f64,
+                        Variable::T => ctx.time,
+                    };
+                    top += 1;
+                }
+                Op::Binary(op) => {
+                    top -= 1;
+                    stack[top - 1] = op.apply(stack[top - 1], stack[top]);
+                }
+                Op::Call(f) => {
+                    let start = top - f.arguments();
+                    stack[start] = f.apply(&stack[start..top]);
+                    top = start + 1;
+                }
+            }
+        }
+        stack[0]
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Number(v) => write!(f, "number {}", v),
+            TokenKind::Name(name) => write!(f, "'{}'", name),
+            TokenKind::Symbol(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+const SYMBOLS: &[&str] = &["<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "^", "(", ")", ",", "<", ">"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // An exponent, as in 1e-3.
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f64>().map_err(|_| ParseError {
+                column,
+                message: format!("invalid number '{}'", text),
+            })?;
+            tokens.push(Token {
+                kind: TokenKind::Number(value),
+                column,
+            });
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Name(chars[start..i].iter().collect()),
+                column,
+            });
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS.iter().find(|s| rest.starts_with(*s)).ok_or_else(|| ParseError {
+                column,
+                message: format!("unexpected character '{}'", c),
+            })?;
+            i += symbol.len();
+            tokens.push(Token {
+                kind: TokenKind::Symbol(symbol),
+                column,
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// How deep the parsed tree may get. Parentheses, function calls, unary minus and
+/// `^` each add a level, as does every further operand in a chain like `a + b + c`.
+/// Parsing, folding and compiling all recurse once per level, so without a limit a
+/// long enough formula would overflow the stack.
+const MAX_NESTING: usize = 256;
+
+/// A recursive descent parser. From lowest to highest precedence: comparisons,
+/// `+ -`, `* / %`, unary minus and `^`, which is right associative and binds
+/// tighter than a minus before it, so `-2^2` is -4.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    end: usize,
+    nesting: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.chars().count() + 1,
+            nesting: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn error_at(&self, column: usize, message: String) -> ParseError {
+        ParseError { column, message }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        let column = self.peek().map_or(self.end, |t| t.column);
+        self.error_at(column, message.to_string())
+    }
+
+    /// Consumes the next token if it is the symbol `s`.
+    fn eat(&mut self, s: &str) -> bool {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Symbol(symbol),
+                ..
+            }) if *symbol == s => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Goes one level deeper into the tree.
+    fn nest(&mut self) -> Result<(), ParseError> {
+        self.nesting += 1;
+        if self.nesting > MAX_NESTING {
+            return Err(self.error("expression is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), ParseError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", s)))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Node, ParseError> {
+        let left = self.sum()?;
+        let comparisons = [
+            ("<=", Binary::LessEqual),
+            (">=", Binary::GreaterEqual),
+            ("==", Binary::Equal),
+            ("!=", Binary::NotEqual),
+            ("<", Binary::Less),
+            (">", Binary::Greater),
+        ];
+        for (symbol, op) in comparisons {
+            if self.eat(symbol) {
+                return Ok(Node::Binary(op, Box::new(left), Box::new(self.sum()?)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Node, ParseError> {
+        let nesting = self.nesting;
+        let mut node = self.product()?;
+        loop {
+            let op = if self.eat("+") {
+                Binary::Add
+            } else if self.eat("-") {
+                Binary::Sub
+            } else {
+                self.nesting = nesting;
+                return Ok(node);
+            };
+            self.nest()?;
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Node, ParseError> {
+        let nesting = self.nesting;
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                Binary::Mul
+            } else if self.eat("/") {
+                Binary::Div
+            } else if self.eat("%") {
+                Binary::Rem
+            } else {
+                self.nesting = nesting;
+                return Ok(node);
+            };
+            self.nest()?;
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    /// Every other level of the grammar is reached through here, so this is where
+    /// nesting is counted.
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        self.nest()?;
+        let node = if self.eat("-") {
+            Node::Call(Function::Neg, vec![self.unary()?])
+        } else if self.eat("+") {
+            self.unary()?
+        } else {
+            let base = self.atom()?;
+            if self.eat("^") {
+                Node::Binary(Binary::Pow, Box::new(base), Box::new(self.unary()?))
+            } else {
+                base
+            }
+        };
+        self.nesting -= 1;
+        Ok(node)
+    }
+
+    fn atom(&mut self) -> Result<Node, ParseError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error("unexpected end of expression")),
+        };
+        self.position += 1;
+        match token.kind {
+            TokenKind::Number(v) => Ok(Node::Number(v)),
+            TokenKind::Symbol("(") => {
+                let node = self.expression()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            TokenKind::Name(name) if self.eat("(") => {
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return Err(self.error("expected ',' or ')'"));
+                        }
+                    }
+                }
+                match Function::lookup(&name, args.len()) {
+                    Some(f) => Ok(Node::Call(f, args)),
+                    None => Err(self.error_at(
+                        token.column,
+                        match Function::arity(&name) {
+                            Some(arity) => format!("'{}' takes {}", name, arity),
+                            None => format!("unknown function '{}'", name),
+                        },
+                    )),
+                }
+            }
+            TokenKind::Name(name) => {
+                let node = match name.as_str() {
+                    "x" => Node::Variable(Variable::X),
+                    "y" => Node::Variable(Variable::Y),
+                    "u" => Node::Variable(Variable::U),
+                    "v" => Node::Variable(Variable::V),
+                    "w" => Node::Variable(Variable::W),
+                    "h" => Node::Variable(Variable::H),
+                    "t" => Node::Variable(Variable::T),
+                    "pi" => Node::Number(std::f64::consts::PI),
+                    "tau" => Node::Number(std::f64::consts::TAU),
+                    "e" => Node::Number(std::f64::consts::E),
+                    _ => {
+                        return Err(self.error_at(
+                            token.column,
+                            format!("unknown variable '{}' (expected x, y, u, v, w, h or t)", name),
+                        ))
+                    }
+                };
+                Ok(node)
+            }
+            kind => Err(self.error_at(token.column, format!("unexpected {}", kind))),
+        }
+    }
+}
+
+/// A shader whose red, green and blue channels are each given by an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprShader {
+    pub r: Expr,
+    pub g: Expr,
+    pub b: Expr,
+}
+
+impl Shader for ExprShader {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        Color::new(self.r.eval(ctx), self.g.eval(ctx), self.b.eval(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> f64 {
+        let ctx = ShadeContext {
+            time: 2.0,
+            ..ShadeContext::new(3.0, 5.0, 11, 21)
+        };
+        Expr::parse(source).unwrap().eval(&ctx)
+    }
+
+    #[test]
+    fn follows_precedence_and_associativity() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("-2 ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+        assert_eq!(eval("-7 % 3"), 2.0);
+        assert_eq!(eval("1 + 1 == 2"), 1.0);
+    }
+
+    #[test]
+    fn reads_variables_and_functions() {
+        assert_eq!(eval("x + y * w + h * t"), 3.0 + 5.0 * 11.0 + 21.0 * 2.0);
+        assert_eq!(eval("u"), 0.3);
+        assert_eq!(eval("v"), 0.25);
+        assert_eq!(eval("mix(10, 20, 0.25)"), 12.5);
+        assert_eq!(eval("clamp(x, 0, 1)"), 1.0);
+        assert_eq!(eval("smoothstep(0, 1, 0.5)"), 0.5);
+        assert_eq!(eval("max(min(x, y), 4)"), 4.0);
+        assert_eq!(eval("cos(pi)"), -1.0);
+        assert!(eval("noise(x, y, t)").abs() <= 1.0);
+    }
+
+    #[test]
+    fn folds_constants() {
+        assert_eq!(Expr::parse("0.25").unwrap().constant(), Some(0.25));
+        assert_eq!(Expr::parse("sqrt(16) / 2 + 2e-1").unwrap().constant(), Some(2.2));
+        assert_eq!(Expr::parse("u * 2").unwrap().constant(), None);
+    }
+
+    #[test]
+    fn reports_errors_with_columns() {
+        let error = |source: &str| Expr::parse(source).unwrap_err();
+        assert_eq!(error("1 + ").column, 5);
+        assert_eq!(error("sin(x").message, "expected ',' or ')'");
+        assert_eq!(error("(u").message, "expected ')'");
+        assert_eq!(error("u + q").column, 5);
+        assert_eq!(error("mix(u, v)").message, "'mix' takes 3 arguments");
+        assert_eq!(error("1 $ 2").message, "unexpected character '$'");
+        assert_eq!(error("(u) v").message, "unexpected 'v'");
+    }
+
+    #[test]
+    fn rejects_deep_nesting_instead_of_overflowing_the_stack() {
+        let nested = |open: &str, n: usize, close: &str| format!("{}u{}", open.repeat(n), close.repeat(n));
+        assert_eq!(eval(&nested("(", 200, ")")), eval("u"));
+        assert_eq!(eval(&nested("-", 200, "")), eval("u"));
+
+        let too_deep = |source: String| Expr::parse(&source).unwrap_err().message;
+        assert_eq!(too_deep(nested("(", 30_000, ")")), "expression is nested too deeply");
+        assert_eq!(too_deep(nested("-", 60_000, "")), "expression is nested too deeply");
+        assert_eq!(too_deep(nested("abs(", 30_000, ")")), "expression is nested too deeply");
+        assert_eq!(too_deep(vec!["u"; 100_000].join("^")), "expression is nested too deeply");
+        assert_eq!(too_deep(vec!["u"; 100_000].join("+")), "expression is nested too deeply");
+        assert_eq!(too_deep(vec!["u"; 100_000].join("*")), "expression is nested too deeply");
+    }
+}
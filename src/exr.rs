@@ -0,0 +1,363 @@
+//! OpenEXR (`.exr`) encoder for single-part scanline images.
+
+use crate::canvas::Canvas;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// The sample type written for each channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    /// 16-bit IEEE 754 half floats.
+    Half,
+    /// 32-bit floats.
+    Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    /// zlib compression of blocks of 16 scanlines.
+    Zip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    pub precision: Precision,
+    pub compression: Compression,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            precision: Precision::Half,
+            compression: Compression::Zip,
+        }
+    }
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> usize {
+        match self {
+            Compression::None => 1,
+            Compression::Zip => 16,
+        }
+    }
+}
+
+/// Writes `canvas` with `B`, `G` and `R` channels holding the unclamped linear values.
+pub fn write<W: Write>(w: &mut W, canvas: &Canvas, options: Options) -> io::Result<()> {
+    let (width, height) = (canvas.width() as usize, canvas.height() as usize);
+    let (type_id, sample_size) = match options.precision {
+        Precision::Half => (1u32, 2),
+        Precision::Float => (2u32, 4),
+    };
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+    header.extend_from_slice(&2u32.to_le_bytes());
+
+    // Channels must be listed in alphabetical order.
+    let mut channels = Vec::new();
+    for name in &["B", "G", "R"] {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&type_id.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+        channels.extend_from_slice(&1u32.to_le_bytes()); // x sampling
+        channels.extend_from_slice(&1u32.to_le_bytes()); // y sampling
+    }
+    channels.push(0);
+
+    let mut window = Vec::new();
+    for &v in &[0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(&mut header, "compression", "compression", &[options.compression.id()]);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    header.push(0);
+
+    // Encode every block up front so the offset table can be written first.
+    let lines = options.compression.lines_per_block();
+    let mut blocks = Vec::new();
+    for (i, rows) in canvas.pixels().chunks(width * lines).enumerate() {
+        let mut raw = Vec::with_capacity(rows.len() * 3 * sample_size);
+        for row in rows.chunks(width) {
+            for channel in 0..3 {
+                for c in row {
+                    let v = match channel {
+                        0 => c.b,
+                        1 => c.g,
+                        _ => c.r,
+                    } as f32;
+                    match options.precision {
+                        Precision::Half => raw.extend_from_slice(&f32_to_half(v).to_le_bytes()),
+                        Precision::Float => raw.extend_from_slice(&v.to_le_bytes()),
+                    }
+                }
+            }
+        }
+
+        let data = match options.compression {
+            Compression::None => raw,
+            Compression::Zip => {
+                // Readers treat a block that is not smaller than its raw size as stored.
+                let packed = zip(&raw);
+                if packed.len() < raw.len() {
+                    packed
+                } else {
+                    raw
+                }
+            }
+        };
+
+        let mut block = Vec::with_capacity(8 + data.len());
+        block.extend_from_slice(&((i * lines) as i32).to_le_bytes());
+        block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        block.extend(data);
+        blocks.push(block);
+    }
+
+    w.write_all(&header)?;
+    let mut offset = (header.len() + 8 * blocks.len()) as u64;
+    for block in &blocks {
+        w.write_all(&offset.to_le_bytes())?;
+        offset += block.len() as u64;
+    }
+    for block in &blocks {
+        w.write_all(block)?;
+    }
+    w.flush()
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// The ZIP codec splits the bytes into even and odd halves, delta encodes them and
+/// then deflates the result.
+fn zip(raw: &[u8]) -> Vec<u8> {
+    let mut reordered = Vec::with_capacity(raw.len());
+    reordered.extend(raw.iter().step_by(2));
+    reordered.extend(raw.iter().skip(1).step_by(2));
+
+    let mut previous = reordered.first().copied().unwrap_or(0);
+    for byte in reordered.iter_mut().skip(1) {
+        let current = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+
+    deflate::deflate_bytes_zlib(&reordered)
+}
+
+/// Converts to IEEE 754 binary16 with round-to-nearest-even.
+pub fn f32_to_half(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity, or a quiet NaN.
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // Subnormal: shift the mantissa, including its implicit leading one, into place.
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round = rest > halfway || (rest == halfway && half & 1 == 1);
+        return sign | (half + round as u32) as u16;
+    }
+
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1fff;
+    let round = rest > 0x1000 || (rest == 0x1000 && half & 1 == 1);
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+    sign | (half + round as u32) as u16
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half" => Ok(Precision::Half),
+            "float" => Ok(Precision::Float),
+            _ => Err(format!("unknown EXR precision '{}' (expected half or float)", s)),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "zip" => Ok(Compression::Zip),
+            _ => Err(format!("unknown EXR compression '{}' (expected none or zip)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use std::convert::TryInto;
+
+    #[test]
+    fn halves_round_to_nearest_even() {
+        assert_eq!(f32_to_half(1.0), 0x3c00);
+        assert_eq!(f32_to_half(-2.0), 0xc000);
+        assert_eq!(f32_to_half(65504.0), 0x7bff);
+        // Halfway between 1 and the next half rounds down to the even mantissa, and
+        // the next halfway point rounds up.
+        assert_eq!(f32_to_half(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_half(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+        // Values past the largest half, including ties, overflow to infinity.
+        assert_eq!(f32_to_half(65520.0), 0x7c00);
+        assert_eq!(f32_to_half(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f32_to_half(f32::NAN) & 0x7e00, 0x7e00);
+        // Subnormals, down to the tie below the smallest one.
+        assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(3.0 * 2f32.powi(-25)), 0x0002);
+        assert_eq!(f32_to_half(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_half(2f32.powi(-14) * (1.0 - 2f32.powi(-12))), 0x0400);
+    }
+
+    #[test]
+    fn uncompressed_scanlines_hold_the_samples() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.5, -2.0));
+        canvas.set_pixel(1, 0, Color::new(65520.0, 0.0, 0.25));
+        let options = Options {
+            precision: Precision::Half,
+            compression: Compression::None,
+        };
+        let mut bytes = Vec::new();
+        write(&mut bytes, &canvas, options).unwrap();
+        assert_eq!(bytes[..8], [0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        // One block: an offset, then the line number, size and B, G, R planes.
+        let block = bytes.len() - 8 - 12;
+        let offset = u64::from_le_bytes(bytes[block - 8..block].try_into().unwrap());
+        assert_eq!(offset, block as u64);
+        let samples: Vec<u16> = bytes[block + 8..].chunks(2).map(|b| u16::from_le_bytes([b[0], b[1]])).collect();
+        assert_eq!(bytes[block..block + 8], [0, 0, 0, 0, 12, 0, 0, 0]);
+        assert_eq!(samples, [0xc000, 0x3400, 0x3800, 0x0000, 0x3c00, 0x7c00]);
+    }
+
+    /// The line number and data of each of the `count` blocks in a file.
+    fn blocks(bytes: &[u8], count: usize) -> Vec<(i32, &[u8])> {
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        // Skip the header's attributes: a name, a type, a size and a value each.
+        let mut at = 8;
+        while bytes[at] != 0 {
+            for _ in 0..2 {
+                at += bytes[at..].iter().position(|&b| b == 0).unwrap() + 1;
+            }
+            at += 4 + word(at) as usize;
+        }
+        (0..count)
+            .map(|i| {
+                let offset = u64::from_le_bytes(bytes[at + 1 + 8 * i..][..8].try_into().unwrap()) as usize;
+                let size = word(offset + 4) as usize;
+                (word(offset) as i32, &bytes[offset + 8..offset + 8 + size])
+            })
+            .collect()
+    }
+
+    /// Inflates a ZIP block and undoes the delta encoding and byte interleaving.
+    fn unzip(data: &[u8]) -> Vec<u8> {
+        let mut reordered = miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap();
+        for i in 1..reordered.len() {
+            reordered[i] = reordered[i].wrapping_add(reordered[i - 1]).wrapping_sub(128);
+        }
+        let (even, odd) = reordered.split_at(reordered.len().div_ceil(2));
+        let mut raw = Vec::with_capacity(reordered.len());
+        for (i, &byte) in even.iter().enumerate() {
+            raw.push(byte);
+            raw.extend(odd.get(i));
+        }
+        raw
+    }
+
+    #[test]
+    fn zip_blocks_inflate_to_the_uncompressed_scanlines() {
+        let mut canvas = Canvas::new(5, 20);
+        canvas.fill(|x, y| Color::new((x / 2) as f64, y as f64 * 0.125, 0.5));
+        let encode = |compression| {
+            let mut bytes = Vec::new();
+            let options = Options {
+                precision: Precision::Half,
+                compression,
+            };
+            write(&mut bytes, &canvas, options).unwrap();
+            bytes
+        };
+        let (plain, zipped) = (encode(Compression::None), encode(Compression::Zip));
+
+        let expected: Vec<u8> = blocks(&plain, 20).iter().flat_map(|&(_, data)| data.to_vec()).collect();
+        // Sixteen lines to a block, each smaller than its raw size so none are stored.
+        let zipped = blocks(&zipped, 2);
+        assert_eq!(zipped.iter().map(|&(line, _)| line).collect::<Vec<_>>(), [0, 16]);
+        assert!(zipped[0].1.len() < 16 * 5 * 3 * 2);
+        let actual: Vec<u8> = zipped.iter().flat_map(|&(_, data)| unzip(data)).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn float_samples_are_written_as_is() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.1, -2.0));
+        canvas.set_pixel(1, 0, Color::new(65520.0, 1e-30, 0.25));
+        let options = Options {
+            precision: Precision::Float,
+            compression: Compression::None,
+        };
+        let mut bytes = Vec::new();
+        write(&mut bytes, &canvas, options).unwrap();
+
+        // Every channel is declared as FLOAT.
+        let float_channel = |name: &str| [name.as_bytes(), &[0, 2, 0, 0, 0]].concat();
+        for name in &["B", "G", "R"] {
+            assert!(bytes.windows(6).any(|w| w == &float_channel(name)[..]), "{}", name);
+        }
+        let block = blocks(&bytes, 1)[0];
+        let samples: Vec<f32> = block.1.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(block.0, 0);
+        assert_eq!(samples, [-2.0, 0.25, 0.1, 1e-30, 1.0, 65520.0]);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{exr, netpbm};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The file formats a canvas can be written in without help from the file name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Tiff,
+    Netpbm(netpbm::Format),
+    /// Radiance RGBE. Like the other floating point formats it stores the canvas
+    /// unclamped and ignores the quantizer.
+    Radiance,
+    /// Portable Float Map.
+    Pfm,
+    OpenExr(exr::Options),
+}
+
+impl OutputFormat {
+    pub const NAMES: &'static [&'static str] = &[
+        "png", "tiff", "ppm", "ppm-ascii", "pgm", "pgm-ascii", "pam", "hdr", "pfm", "exr",
+    ];
+
+    /// Picks a format from the extension of `path`. Returns `None` for extensions
+    /// that are left for the `image` crate to handle.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<OutputFormat> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "hdr" => Some(OutputFormat::Radiance),
+            "pfm" => Some(OutputFormat::Pfm),
+            "exr" => Some(OutputFormat::OpenExr(exr::Options::default())),
+            _ => netpbm::Format::from_extension(ext).map(OutputFormat::Netpbm),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(OutputFormat::Png),
+            "tiff" => Ok(OutputFormat::Tiff),
+            "hdr" => Ok(OutputFormat::Radiance),
+            "pfm" => Ok(OutputFormat::Pfm),
+            "exr" => Ok(OutputFormat::OpenExr(exr::Options::default())),
+            _ => s
+                .parse()
+                .map(OutputFormat::Netpbm)
+                .map_err(|_| format!("unknown output format '{}' (expected one of: {})", s, Self::NAMES.join(", "))),
+        }
+    }
+}
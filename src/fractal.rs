@@ -0,0 +1,612 @@
+//! Escape-time fractals: the Mandelbrot set, Julia sets and the Burning Ship.
+//!
+//! Each pixel is a point of the complex plane that is iterated until it escapes a
+//! circle of radius [`bailout`](EscapeTime::bailout) or the iteration limit is
+//! reached. Escaped points are colored by a smoothed iteration count and the rest
+//! get the interior color, or every point is colored by how close its orbit came
+//! to a trap.
+//!
+//! Plain `f64` pixels start to blur at zooms of about 10^10 and run out of
+//! precision entirely near 10^13. Beyond 10^10 a reference orbit is computed once
+//! in double-double precision at the center of the view, and every pixel iterates
+//! only its small difference from that orbit (perturbation), which `f64` handles
+//! down to zooms of about 10^30.
+
+use crate::color::Color;
+use crate::colormap::Colormap;
+use crate::gradient::Spread;
+use crate::shader::{ShadeContext, Shader};
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// A number stored as the unevaluated sum of two `f64`s, giving about 106 bits of
+/// mantissa. Enough for the center of a deep zoom.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    pub const fn new(v: f64) -> DoubleDouble {
+        DoubleDouble { hi: v, lo: 0.0 }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn abs(self) -> DoubleDouble {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Renormalizes a sum whose parts may overlap, assuming `|a| >= |b|`.
+    fn quick_two_sum(a: f64, b: f64) -> DoubleDouble {
+        let hi = a + b;
+        DoubleDouble { hi, lo: b - (hi - a) }
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(v: f64) -> DoubleDouble {
+        DoubleDouble::new(v)
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        // Knuth's two-sum keeps the rounding error of the high parts exactly.
+        let s = self.hi + other.hi;
+        let v = s - self.hi;
+        let e = (self.hi - (s - v)) + (other.hi - v);
+        DoubleDouble::quick_two_sum(s, e + self.lo + other.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        // A fused multiply-add gives the exact error of the product of the high parts.
+        let p = self.hi * other.hi;
+        let e = self.hi.mul_add(other.hi, -p);
+        DoubleDouble::quick_two_sum(p, e + self.hi * other.lo + self.lo * other.hi)
+    }
+}
+
+impl FromStr for DoubleDouble {
+    type Err = String;
+
+    /// Parses a decimal number such as `-0.74364388703715870475219150611477` or
+    /// `1.5e-20`, keeping all the digits a double-double can hold.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // Let the standard parser reject anything malformed.
+        match s.parse::<f64>() {
+            Ok(v) if v.is_finite() => (),
+            _ => return Err(format!("invalid number '{}'", s)),
+        }
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().map_err(|_| format!("invalid number '{}'", s))?),
+            None => (s, 0),
+        };
+        let (negative, digits) = match mantissa.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+
+        let ten = DoubleDouble::new(10.0);
+        let mut value = DoubleDouble::default();
+        let mut scale = exponent;
+        let mut fraction = false;
+        for c in digits.chars() {
+            match c {
+                '.' => fraction = true,
+                _ => {
+                    let digit = c.to_digit(10).ok_or_else(|| format!("invalid number '{}'", s))?;
+                    value = value * ten + DoubleDouble::new(digit as f64);
+                    if fraction {
+                        scale -= 1;
+                    }
+                }
+            }
+        }
+
+        // Powers of ten up to 10^22 are exact in f64, so step by at most that much.
+        while scale != 0 {
+            let step = scale.clamp(-22, 22);
+            let power = DoubleDouble::new(10f64.powi(step.abs()));
+            value = if step > 0 { value * power } else { divide(value, power) };
+            scale -= step;
+        }
+        Ok(if negative { -value } else { value })
+    }
+}
+
+fn divide(a: DoubleDouble, b: DoubleDouble) -> DoubleDouble {
+    // Long division, one f64 quotient digit at a time.
+    let q1 = a.hi / b.hi;
+    let r = a - b * DoubleDouble::new(q1);
+    let q2 = r.hi / b.hi;
+    let r = r - b * DoubleDouble::new(q2);
+    let q3 = r.hi / b.hi;
+    DoubleDouble::quick_two_sum(q1, q2) + DoubleDouble::new(q3)
+}
+
+/// The iterated function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formula {
+    /// `z = z² + c`, with `z` starting at 0 and `c` the pixel.
+    Mandelbrot,
+    /// `z = z² + c`, with `z` starting at the pixel and a fixed `c`.
+    Julia { c: (f64, f64) },
+    /// `z = (|Re z| + i|Im z|)² + c`. The imaginary axis points down, so the ship
+    /// sails upright.
+    BurningShip,
+}
+
+impl Formula {
+    /// One step of the iteration on an `f64` point.
+    fn step(self, (x, y): (f64, f64), (cx, cy): (f64, f64)) -> (f64, f64) {
+        match self {
+            Formula::Mandelbrot | Formula::Julia { .. } => (x * x - y * y + cx, 2.0 * x * y + cy),
+            Formula::BurningShip => (x * x - y * y + cx, 2.0 * (x * y).abs() + cy),
+        }
+    }
+
+    /// One step of the perturbed iteration: the new offset from the reference orbit,
+    /// given the reference point `(rx, ry)` and the current offset `(x, y)`.
+    fn perturb(self, (rx, ry): (f64, f64), (x, y): (f64, f64), (cx, cy): (f64, f64)) -> (f64, f64) {
+        let re = (2.0 * rx + x) * x - (2.0 * ry + y) * y + cx;
+        let im = match self {
+            Formula::Mandelbrot | Formula::Julia { .. } => 2.0 * (rx * y + ry * x + x * y) + cy,
+            Formula::BurningShip => 2.0 * diff_abs(rx * ry, rx * y + ry * x + x * y) + cy,
+        };
+        (re, im)
+    }
+}
+
+/// `|a + d| - |a|`, computed without cancellation when `d` is tiny.
+fn diff_abs(a: f64, d: f64) -> f64 {
+    match (a >= 0.0, a + d >= 0.0) {
+        (true, true) => d,
+        (true, false) => -(2.0 * a + d),
+        (false, true) => 2.0 * a + d,
+        (false, false) => -d,
+    }
+}
+
+/// A shape that orbits are colored by their closest distance to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trap {
+    Point { center: (f64, f64) },
+    /// The two lines through `center` parallel to the axes.
+    Cross { center: (f64, f64) },
+    /// A circle around the origin.
+    Circle { radius: f64 },
+}
+
+impl Trap {
+    fn distance(self, (x, y): (f64, f64)) -> f64 {
+        match self {
+            Trap::Point { center } => (x - center.0).hypot(y - center.1),
+            Trap::Cross { center } => (x - center.0).abs().min((y - center.1).abs()),
+            Trap::Circle { radius } => (x.hypot(y) - radius).abs(),
+        }
+    }
+}
+
+/// What a point is colored by.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Coloring {
+    /// The continuous iteration count, without visible bands.
+    #[default]
+    Smooth,
+    /// The whole iteration count, in bands.
+    Iterations,
+    /// The closest distance from the orbit to a trap. Interior points are colored
+    /// this way too.
+    Trap(Trap),
+}
+
+/// When to switch to perturbation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Precision {
+    /// Perturbation for zooms past 10^10, where `f64` pixels start to blur.
+    #[default]
+    Auto,
+    Double,
+    Perturbation,
+}
+
+/// An escape-time fractal shader.
+#[derive(Debug)]
+pub struct EscapeTime {
+    pub formula: Formula,
+    /// The point at the center of the image.
+    pub center: (DoubleDouble, DoubleDouble),
+    /// At zoom 1 the shorter side of the image spans 3 units of the plane.
+    pub zoom: f64,
+    pub max_iterations: u32,
+    pub bailout: f64,
+    pub precision: Precision,
+    pub coloring: Coloring,
+    pub colormap: Colormap,
+    /// How many iterations one pass through the colormap takes. Trap distances are
+    /// divided by it too.
+    pub cycle: f64,
+    pub spread: Spread,
+    /// The color of points that never escape.
+    pub interior: Color,
+    reference: OnceLock<Vec<(f64, f64)>>,
+}
+
+impl EscapeTime {
+    /// A view of the whole fractal with 256 iterations and a black and white map.
+    pub fn new(formula: Formula) -> EscapeTime {
+        let center = match formula {
+            Formula::Mandelbrot => (-0.5, 0.0),
+            Formula::Julia { .. } => (0.0, 0.0),
+            Formula::BurningShip => (-0.45, -0.5),
+        };
+        EscapeTime {
+            formula,
+            center: (center.0.into(), center.1.into()),
+            zoom: 1.0,
+            max_iterations: 256,
+            bailout: 256.0,
+            precision: Precision::Auto,
+            coloring: Coloring::Smooth,
+            colormap: Colormap::Gray,
+            cycle: 64.0,
+            spread: Spread::Reflect,
+            interior: Color::BLACK,
+            reference: OnceLock::new(),
+        }
+    }
+
+    fn uses_perturbation(&self) -> bool {
+        match self.precision {
+            Precision::Auto => self.zoom > 1e10,
+            Precision::Double => false,
+            Precision::Perturbation => true,
+        }
+    }
+
+    /// The offset of a pixel from the center of the view, in units of the plane.
+    fn offset(&self, ctx: &ShadeContext) -> (f64, f64) {
+        let size = 3.0 / self.zoom / ctx.width.min(ctx.height) as f64;
+        let dx = ctx.x - (ctx.width as f64 - 1.0) / 2.0;
+        let dy = (ctx.height as f64 - 1.0) / 2.0 - ctx.y;
+        let dy = match self.formula {
+            Formula::BurningShip => -dy,
+            _ => dy,
+        };
+        (dx * size, dy * size)
+    }
+
+    /// The orbit of the center, computed in double-double precision and rounded to
+    /// `f64`, up to and including the point where it escapes.
+    fn reference(&self) -> &[(f64, f64)] {
+        self.reference.get_or_init(|| {
+            let (mut x, mut y, cx, cy) = match self.formula {
+                Formula::Julia { c } => (self.center.0, self.center.1, c.0.into(), c.1.into()),
+                _ => (DoubleDouble::default(), DoubleDouble::default(), self.center.0, self.center.1),
+            };
+            let two = DoubleDouble::new(2.0);
+            let mut orbit = vec![(x.to_f64(), y.to_f64())];
+            for _ in 0..self.max_iterations {
+                let xy = match self.formula {
+                    Formula::BurningShip => (x * y).abs(),
+                    _ => x * y,
+                };
+                (x, y) = (x * x - y * y + cx, two * xy + cy);
+                let point = (x.to_f64(), y.to_f64());
+                orbit.push(point);
+                if point.0 * point.0 + point.1 * point.1 > self.bailout * self.bailout {
+                    break;
+                }
+            }
+            orbit
+        })
+    }
+
+    /// Iterates a pixel, calling `visit` with every point of its orbit. Returns the
+    /// iteration at which it escaped and the first point outside the bailout circle.
+    fn iterate(&self, ctx: &ShadeContext, mut visit: impl FnMut((f64, f64))) -> Option<(u32, (f64, f64))> {
+        let bailout = self.bailout * self.bailout;
+        let (dx, dy) = self.offset(ctx);
+
+        if !self.uses_perturbation() {
+            let pixel = (self.center.0.to_f64() + dx, self.center.1.to_f64() + dy);
+            let (mut z, c) = match self.formula {
+                Formula::Julia { c } => (pixel, c),
+                _ => ((0.0, 0.0), pixel),
+            };
+            for n in 1..=self.max_iterations {
+                z = self.formula.step(z, c);
+                visit(z);
+                if z.0 * z.0 + z.1 * z.1 > bailout {
+                    return Some((n, z));
+                }
+            }
+            return None;
+        }
+
+        // Zhuoran's rebasing: whenever the pixel gets closer to the start of the
+        // reference orbit than to the current reference point, or the reference
+        // escapes, restart from the beginning of the reference. This avoids the
+        // glitches of classic perturbation without needing extra references.
+        let reference = self.reference();
+        let (mut delta, dc) = match self.formula {
+            Formula::Julia { .. } => ((dx, dy), (0.0, 0.0)),
+            _ => ((0.0, 0.0), (dx, dy)),
+        };
+        let mut m = 0;
+        for n in 1..=self.max_iterations {
+            delta = self.formula.perturb(reference[m], delta, dc);
+            m += 1;
+            let z = (reference[m].0 + delta.0, reference[m].1 + delta.1);
+            visit(z);
+            let size = z.0 * z.0 + z.1 * z.1;
+            if size > bailout {
+                return Some((n, z));
+            }
+            if size < delta.0 * delta.0 + delta.1 * delta.1 || m == reference.len() - 1 {
+                delta = (z.0 - reference[0].0, z.1 - reference[0].1);
+                m = 0;
+            }
+        }
+        None
+    }
+}
+
+impl Shader for EscapeTime {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        if let Coloring::Trap(trap) = self.coloring {
+            let mut closest = f64::INFINITY;
+            self.iterate(ctx, |z| closest = closest.min(trap.distance(z)));
+            return self.colormap.at(self.spread.apply(closest / self.cycle));
+        }
+
+        let (n, z) = match self.iterate(ctx, |_| ()) {
+            Some(escaped) => escaped,
+            None => return self.interior,
+        };
+        let t = match self.coloring {
+            // The fractional part estimates how far past the bailout circle the last
+            // step went, which makes the count continuous across band edges.
+            Coloring::Smooth => {
+                let log_size = 0.5 * (z.0 * z.0 + z.1 * z.1).ln();
+                n as f64 + 1.0 - (log_size / self.bailout.ln()).log2()
+            }
+            _ => n as f64,
+        };
+        self.colormap.at(self.spread.apply(t / self.cycle))
+    }
+}
+
+impl FromStr for Coloring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smooth" => Ok(Coloring::Smooth),
+            "iterations" => Ok(Coloring::Iterations),
+            _ => Err(format!("unknown coloring '{}' (expected smooth or iterations)", s)),
+        }
+    }
+}
+
+impl FromStr for Trap {
+    type Err = String;
+
+    /// Accepts `point`, `cross` and `circle`, optionally followed by `:x,y` for the
+    /// center of a point or cross, or `:r` for the radius of a circle.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (s, None),
+        };
+        let center = || arg.map_or(Ok((0.0, 0.0)), crate::params::parse_pair);
+        match kind {
+            "point" => Ok(Trap::Point { center: center()? }),
+            "cross" => Ok(Trap::Cross { center: center()? }),
+            "circle" => match arg.map_or(Ok(1.0), str::parse::<f64>) {
+                Ok(radius) if radius.is_finite() && radius >= 0.0 => Ok(Trap::Circle { radius }),
+                _ => Err("expected a circle radius".to_string()),
+            },
+            _ => Err(format!("unknown trap '{}' (expected point, cross or circle)", s)),
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Precision::Auto),
+            "double" => Ok(Precision::Double),
+            "perturbation" => Ok(Precision::Perturbation),
+            _ => Err(format!("unknown precision '{}' (expected auto, double or perturbation)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_more_digits_than_f64_holds() {
+        let v: DoubleDouble = "0.1000000000000000000000000000001".parse().unwrap();
+        // The nearest f64 to 0.1 is 0.1000000000000000055511151231257827...
+        let expected = 1e-31 - 5.551115123125783e-18;
+        assert!(((v - DoubleDouble::new(0.1)).to_f64() - expected).abs() < 1e-32);
+        let v: DoubleDouble = "-2.5e-3".parse().unwrap();
+        assert_eq!(v.to_f64(), -0.0025);
+    }
+
+    #[test]
+    fn perturbation_matches_direct_iteration() {
+        for formula in [Formula::Mandelbrot, Formula::Julia { c: (-0.8, 0.156) }, Formula::BurningShip] {
+            let mut fractal = EscapeTime::new(formula);
+            fractal.center = ((-0.7435).into(), (0.1314).into());
+            fractal.zoom = 200.0;
+            fractal.coloring = Coloring::Iterations;
+            let direct: Vec<_> = (0..32)
+                .map(|i| fractal.shade(&ShadeContext::new(i as f64 * 2.0, i as f64, 64, 32)))
+                .collect();
+
+            fractal.precision = Precision::Perturbation;
+            for (i, expected) in direct.into_iter().enumerate() {
+                let ctx = ShadeContext::new(i as f64 * 2.0, i as f64, 64, 32);
+                assert_eq!(fractal.shade(&ctx), expected, "{:?} pixel {}", formula, i);
+            }
+        }
+    }
+
+    /// Checks that the single pixel of a view centered on `c` gets the colormap's
+    /// color for `expected`, before it is divided by the cycle.
+    fn assert_value(fractal: &mut EscapeTime, c: (f64, f64), expected: f64) {
+        fractal.center = (c.0.into(), c.1.into());
+        let actual = fractal.shade(&ShadeContext::new(0.0, 0.0, 1, 1));
+        let wanted = fractal.colormap.at(fractal.spread.apply(expected / fractal.cycle));
+        let error = (actual.r - wanted.r).abs().max((actual.g - wanted.g).abs()).max((actual.b - wanted.b).abs());
+        assert!(error < 1e-9, "{:?} at {:?}: {:?} vs {:?}", fractal.coloring, c, actual, wanted);
+    }
+
+    #[test]
+    fn smooth_coloring_interpolates_between_bands() {
+        let mut fractal = EscapeTime::new(Formula::Mandelbrot);
+        fractal.spread = Spread::Pad;
+        fractal.cycle = 8.0;
+
+        // c = 2 goes 2, 6 and escapes a bailout of 4 at the second step, 6 being
+        // log2(ln 6 / ln 4) of the way to the next band.
+        fractal.bailout = 4.0;
+        assert_value(&mut fractal, (2.0, 0.0), 2.629_856_648_053_998_7);
+
+        // Either side of the first band edge the whole counts differ by one, but the
+        // smooth values are both within a thousandth of 2.
+        fractal.bailout = 256.0;
+        let smooth = |n: f64, z: f64| n + 1.0 - (z.ln() / 256f64.ln()).log2();
+        assert_value(&mut fractal, (256.001, 0.0), smooth(1.0, 256.001));
+        assert_value(&mut fractal, (255.999, 0.0), smooth(2.0, 255.999 * 255.999 + 255.999));
+        assert!((smooth(1.0, 256.001) - smooth(2.0, 255.999 * 255.999 + 255.999)).abs() < 1e-3);
+        fractal.coloring = Coloring::Iterations;
+        assert_value(&mut fractal, (256.001, 0.0), 1.0);
+        assert_value(&mut fractal, (255.999, 0.0), 2.0);
+    }
+
+    #[test]
+    fn traps_take_the_closest_distance_of_the_orbit() {
+        let mut fractal = EscapeTime::new(Formula::Mandelbrot);
+        fractal.spread = Spread::Pad;
+        fractal.cycle = 1.0;
+
+        // c = -1 never escapes: its orbit alternates between -1 and 0.
+        let traps = [
+            (Trap::Point { center: (0.5, 0.5) }, 0.5f64.hypot(0.5)),
+            (Trap::Cross { center: (0.2, 0.3) }, 0.2),
+            (Trap::Circle { radius: 0.4 }, 0.4),
+            (Trap::Circle { radius: 0.9 }, 0.1),
+        ];
+        for &(trap, distance) in &traps {
+            fractal.coloring = Coloring::Trap(trap);
+            assert_value(&mut fractal, (-1.0, 0.0), distance);
+        }
+
+        // The point that escapes counts too: c = 2 goes 2, 6.
+        fractal.bailout = 4.0;
+        fractal.coloring = Coloring::Trap(Trap::Point { center: (5.0, 0.0) });
+        assert_value(&mut fractal, (2.0, 0.0), 1.0);
+    }
+
+    /// The escape iteration of the Mandelbrot set at `c`, iterated entirely in
+    /// double-double precision.
+    fn escape_double_double(c: (DoubleDouble, DoubleDouble), max_iterations: u32, bailout: f64) -> Option<u32> {
+        let (mut x, mut y) = (DoubleDouble::default(), DoubleDouble::default());
+        let two = DoubleDouble::new(2.0);
+        for n in 1..=max_iterations {
+            (x, y) = (x * x - y * y + c.0, two * x * y + c.1);
+            let (fx, fy) = (x.to_f64(), y.to_f64());
+            if fx * fx + fy * fy > bailout * bailout {
+                return Some(n);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn perturbation_resolves_deep_zooms() {
+        // Seahorse valley at 10^20, far past what f64 coordinates can tell apart.
+        let mut fractal = EscapeTime::new(Formula::Mandelbrot);
+        fractal.center = (
+            "-0.743643887037158704752191506114774".parse().unwrap(),
+            "0.131825904205311970493132056385139".parse().unwrap(),
+        );
+        fractal.zoom = 1e20;
+        fractal.max_iterations = 20_000;
+        assert!(fractal.uses_perturbation());
+
+        let (width, height) = (24, 16);
+        let pixels: Vec<ShadeContext> = (0..width * height)
+            .map(|i| ShadeContext::new((i % width) as f64, (i / width) as f64, width, height))
+            .collect();
+        let mut exact = 0;
+        for ctx in &pixels {
+            let (dx, dy) = fractal.offset(ctx);
+            let c = (fractal.center.0 + dx.into(), fractal.center.1 + dy.into());
+            let expected = escape_double_double(c, fractal.max_iterations, fractal.bailout).unwrap();
+            let actual = fractal.iterate(ctx, |_| ()).map(|(n, _)| n).unwrap();
+            // After thousands of iterations the orbits near the boundary are chaotic,
+            // so both methods' rounding can decide a few pixels differently.
+            let difference = (actual as f64 - expected as f64).abs() / expected as f64;
+            assert!(difference < 0.02, "{} iterations instead of {} at {:?}", actual, expected, (ctx.x, ctx.y));
+            exact += (actual == expected) as usize;
+        }
+        assert!(exact * 100 >= pixels.len() * 95, "only {} of {} pixels match", exact, pixels.len());
+
+        // Plain f64 cannot resolve this view: neighbouring pixels round to the same
+        // coordinates and come out alike.
+        let distinct = |fractal: &EscapeTime| {
+            let mut counts: Vec<_> = pixels.iter().map(|ctx| fractal.iterate(ctx, |_| ()).map(|(n, _)| n)).collect();
+            counts.sort();
+            counts.dedup();
+            counts.len()
+        };
+        let deep = distinct(&fractal);
+        fractal.precision = Precision::Double;
+        let flat = distinct(&fractal);
+        assert!(deep > 10 * flat, "{} distinct counts with perturbation, {} without", deep, flat);
+    }
+}
@@ -0,0 +1,557 @@
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::colormap::{Colormap, Colormapped};
+use crate::dither::parse_hex;
+use crate::error::{Error, Result};
+use crate::expr::{Expr, ExprShader};
+use crate::fractal::{Coloring, DoubleDouble, EscapeTime, Formula};
+use crate::gradient::{decode_srgb, parse_stops, Gradient, Ramp, Shape};
+use crate::integrator::PathTracer;
+use crate::mesh::Mesh;
+use crate::noise::{Fractal, FractalKind, Noise, NoiseField, OpenSimplex, Perlin, Value, Warp, Worley};
+use crate::params::{parse_pair, parse_vec3, Params};
+use crate::scene::{Scene, SceneShader};
+use crate::shader::{Shader, UvGradient};
+use crate::sky::{Sky, SkyShader};
+use crate::transfer::Transfer;
+use crate::vec3::Vec3;
+use std::str::FromStr;
+
+/// The built-in pixel generators that can be selected by name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    Gradient,
+    Sky,
+    Scene,
+    LinearGradient,
+    RadialGradient,
+    ConicGradient,
+    DiamondGradient,
+    Perlin,
+    OpenSimplex,
+    ValueNoise,
+    Worley,
+    Mandelbrot,
+    Julia,
+    BurningShip,
+    Expression,
+}
+
+const DEFAULT_STOPS: &str = "#000000,#ffffff";
+
+impl Generator {
+    pub const ALL: &'static [Generator] = &[
+        Generator::Gradient,
+        Generator::Sky,
+        Generator::Scene,
+        Generator::LinearGradient,
+        Generator::RadialGradient,
+        Generator::ConicGradient,
+        Generator::DiamondGradient,
+        Generator::Perlin,
+        Generator::OpenSimplex,
+        Generator::ValueNoise,
+        Generator::Worley,
+        Generator::Mandelbrot,
+        Generator::Julia,
+        Generator::BurningShip,
+        Generator::Expression,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Generator::Gradient => "gradient",
+            Generator::Sky => "sky",
+            Generator::Scene => "scene",
+            Generator::LinearGradient => "linear-gradient",
+            Generator::RadialGradient => "radial-gradient",
+            Generator::ConicGradient => "conic-gradient",
+            Generator::DiamondGradient => "diamond-gradient",
+            Generator::Perlin => "perlin",
+            Generator::OpenSimplex => "opensimplex",
+            Generator::ValueNoise => "value-noise",
+            Generator::Worley => "worley",
+            Generator::Mandelbrot => "mandelbrot",
+            Generator::Julia => "julia",
+            Generator::BurningShip => "burning-ship",
+            Generator::Expression => "expression",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Generator::Gradient => "Red along x, green along y, constant blue",
+            Generator::Sky => "A sky gradient seen through a pinhole camera",
+            Generator::Scene => "Path-traced objects in front of the sky",
+            Generator::LinearGradient => "Color stops along a line at any angle",
+            Generator::RadialGradient => "Color stops in circles around a center",
+            Generator::ConicGradient => "Color stops swept around a center",
+            Generator::DiamondGradient => "Color stops in diamonds around a center",
+            Generator::Perlin => "Perlin gradient noise",
+            Generator::OpenSimplex => "OpenSimplex noise, smoother and more isotropic than Perlin",
+            Generator::ValueNoise => "Smoothly interpolated random values",
+            Generator::Worley => "Cellular noise from distances to random points",
+            Generator::Mandelbrot => "The Mandelbrot set",
+            Generator::Julia => "The Julia set for a constant c",
+            Generator::BurningShip => "The Burning Ship fractal",
+            Generator::Expression => "Red, green and blue given as formulas of x, y, u, v, w, h and t",
+        }
+    }
+
+    /// The parameters this generator accepts with `-p key=value`.
+    pub fn parameters(self) -> &'static [&'static str] {
+        match self {
+            Generator::Gradient => &[],
+            Generator::Sky => &["position", "look-at", "up", "fov", "horizon", "zenith"],
+            Generator::Scene => &[
+                "scene", "mesh", "shading", "depth", "roulette", "position", "look-at", "up", "fov", "horizon",
+                "zenith",
+            ],
+            Generator::LinearGradient => &["stops", "colormap", "spread", "interpolation", "angle"],
+            Generator::RadialGradient | Generator::DiamondGradient => {
+                &["stops", "colormap", "spread", "interpolation", "center", "radius"]
+            }
+            Generator::ConicGradient => &["stops", "colormap", "spread", "interpolation", "center", "angle"],
+            Generator::Perlin | Generator::OpenSimplex | Generator::ValueNoise => NOISE_PARAMETERS,
+            Generator::Worley => WORLEY_PARAMETERS,
+            Generator::Mandelbrot | Generator::BurningShip => FRACTAL_PARAMETERS,
+            Generator::Julia => JULIA_PARAMETERS,
+            Generator::Expression => &["r", "g", "b"],
+        }
+    }
+
+    /// The transfer function the output should be encoded with unless another one
+    /// is asked for. Color stops and colormaps are sRGB colors turned into linear
+    /// light, and path traced scenes compute linear radiance, so they only look
+    /// right when the output is sRGB-encoded. Other generators write their values as
+    /// they are, like the book.
+    pub fn default_transfer(self) -> Transfer {
+        match self {
+            Generator::Gradient | Generator::Sky | Generator::Expression => Transfer::PassThrough,
+            _ => Transfer::Srgb,
+        }
+    }
+
+    /// The shader that draws this generator with the given parameters.
+    pub fn shader(self, params: &Params) -> Result<Box<dyn Shader>> {
+        params.check_known(self.name(), self.parameters())?;
+        let center = || -> Result<(f64, f64)> {
+            Ok(params.get_with("center", parse_pair)?.unwrap_or((0.5, 0.5)))
+        };
+        let radius = || {
+            params.get_with("radius", |v| match v.parse::<f64>() {
+                Ok(r) if r > 0.0 && r.is_finite() => Ok(r),
+                _ => Err("expected a positive number".to_string()),
+            })
+        };
+
+        let shape = match self {
+            Generator::Gradient => return Ok(Box::new(UvGradient::default())),
+            Generator::Sky => {
+                let book = Scene::book();
+                return Ok(Box::new(SkyShader {
+                    camera: camera(params, &book)?,
+                    sky: sky(params, book.sky, self.default_transfer())?,
+                }));
+            }
+            Generator::Scene => {
+                let scene = match (params.raw("mesh"), params.raw("scene")) {
+                    (Some(_), Some(_)) => {
+                        return Err(Error::Usage("give either 'scene' or 'mesh', not both".to_string()));
+                    }
+                    (Some(path), None) => Scene::mesh(Mesh::load(path)?),
+                    (None, name) => {
+                        let name = name.unwrap_or("book");
+                        Scene::builtin(name).ok_or_else(|| {
+                            Error::Usage(format!(
+                                "unknown scene '{}' (expected {})",
+                                name,
+                                Scene::NAMES.join(" or ")
+                            ))
+                        })?
+                    }
+                };
+                let mut tracer = PathTracer::default();
+                let bounces = |key, default| {
+                    let value = params.get_with(key, |v| match v.parse::<u32>() {
+                        Ok(n) if n > 0 => Ok(n),
+                        _ => Err("expected a positive whole number".to_string()),
+                    })?;
+                    Ok::<_, Error>(value.unwrap_or(default))
+                };
+                tracer.max_depth = bounces("depth", tracer.max_depth)?;
+                tracer.roulette_depth = bounces("roulette", tracer.roulette_depth)?;
+                return Ok(Box::new(SceneShader {
+                    camera: camera(params, &scene)?,
+                    sky: sky(params, scene.sky, self.default_transfer())?,
+                    shading: params.get_or("shading", Default::default())?,
+                    world: Bvh::new(scene.world.objects),
+                    tracer,
+                }));
+            }
+            Generator::Expression => {
+                let channel = |key| -> Result<Expr> { Ok(params.get(key)?.unwrap_or_else(|| "0".parse().unwrap())) };
+                return Ok(Box::new(ExprShader {
+                    r: channel("r")?,
+                    g: channel("g")?,
+                    b: channel("b")?,
+                }));
+            }
+            Generator::Perlin | Generator::OpenSimplex | Generator::ValueNoise | Generator::Worley => {
+                return self.noise_shader(params);
+            }
+            Generator::Mandelbrot | Generator::Julia | Generator::BurningShip => {
+                return self.fractal_shader(params);
+            }
+            Generator::LinearGradient => Shape::Linear {
+                angle: params.number("angle", 0.0)?,
+            },
+            Generator::RadialGradient => Shape::Radial {
+                center: center()?,
+                radius: radius()?,
+            },
+            Generator::ConicGradient => Shape::Conic {
+                center: center()?,
+                angle: params.number("angle", 0.0)?,
+            },
+            Generator::DiamondGradient => Shape::Diamond {
+                center: center()?,
+                radius: radius()?,
+            },
+        };
+        let colormap = colormap(params)?;
+        Ok(Box::new(Gradient {
+            shape,
+            colormap,
+            spread: params.get_or("spread", Default::default())?,
+        }))
+    }
+
+    /// A noise generator, optionally layered into a fractal and domain warped with
+    /// fBm OpenSimplex noise.
+    fn noise_shader(self, params: &Params) -> Result<Box<dyn Shader>> {
+        let seed = params.get_or("seed", 0u64)?;
+
+        let mut noise: Box<dyn Noise> = match self {
+            Generator::Perlin => Box::new(Perlin { seed }),
+            Generator::OpenSimplex => Box::new(OpenSimplex { seed }),
+            Generator::ValueNoise => Box::new(Value { seed }),
+            _ => Box::new(Worley {
+                seed,
+                metric: params.get_or("metric", Default::default())?,
+                feature: params.get_or("feature", Default::default())?,
+            }),
+        };
+        let fractal = params.get_with("fractal", |v| match v {
+            "none" => Ok(None),
+            _ => v.parse::<FractalKind>().map(Some),
+        })?;
+        if let Some(kind) = fractal.flatten() {
+            let mut fractal = Fractal::new(noise, kind);
+            let octaves = params.get_with("octaves", |v| match v.parse::<u32>() {
+                Ok(n) if (1..=16).contains(&n) => Ok(n),
+                _ => Err("expected a whole number from 1 to 16".to_string()),
+            })?;
+            fractal.octaves = octaves.unwrap_or(fractal.octaves);
+            fractal.lacunarity = positive(params, "lacunarity", fractal.lacunarity)?;
+            fractal.gain = positive(params, "gain", fractal.gain)?;
+            noise = Box::new(fractal);
+        }
+        let warp = params.number("warp", 0.0)?;
+        if warp != 0.0 {
+            let warp_noise = Fractal::new(OpenSimplex { seed: seed.wrapping_add(1) }, FractalKind::Fbm);
+            noise = Box::new(Warp {
+                noise,
+                warp: warp_noise,
+                strength: warp,
+            });
+        }
+
+        let dimensions = params.get_with("dimensions", |v| match v.parse::<u32>() {
+            Ok(d) if (2..=4).contains(&d) => Ok(d),
+            _ => Err("expected 2, 3 or 4".to_string()),
+        })?;
+        let (min, max) = noise.range();
+        let field = NoiseField {
+            noise,
+            scale: positive(params, "scale", 64.0)?,
+            dimensions: dimensions.unwrap_or(2),
+            period: positive(params, "period", 1.0)?,
+        };
+        Ok(Box::new(Colormapped {
+            min,
+            max,
+            ..Colormapped::new(field, colormap(params)?)
+        }))
+    }
+
+    /// An escape-time fractal. The center is parsed in double-double precision so
+    /// that deep zooms can be given exactly.
+    fn fractal_shader(self, params: &Params) -> Result<Box<dyn Shader>> {
+        let formula = match self {
+            Generator::Mandelbrot => Formula::Mandelbrot,
+            Generator::Julia => Formula::Julia {
+                c: params.get_with("c", parse_pair)?.unwrap_or((-0.8, 0.156)),
+            },
+            _ => Formula::BurningShip,
+        };
+        let mut fractal = EscapeTime::new(formula);
+
+        let center = params.get_with("center", |v| match v.split_once(',') {
+            Some((x, y)) => Ok((x.parse::<DoubleDouble>()?, y.parse::<DoubleDouble>()?)),
+            None => Err("expected two numbers as x,y".to_string()),
+        })?;
+        fractal.center = center.unwrap_or(fractal.center);
+        fractal.zoom = positive(params, "zoom", fractal.zoom)?;
+        let iterations = params.get_with("iterations", |v| match v.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err("expected a positive whole number".to_string()),
+        })?;
+        fractal.max_iterations = iterations.unwrap_or(fractal.max_iterations);
+        fractal.bailout = params
+            .get_with("bailout", |v| match v.parse::<f64>() {
+                Ok(b) if b >= 2.0 && b.is_finite() => Ok(b),
+                _ => Err("expected a number of at least 2".to_string()),
+            })?
+            .unwrap_or(fractal.bailout);
+        fractal.precision = params.get_or("precision", fractal.precision)?;
+        fractal.coloring = match params.get("trap")? {
+            Some(trap) => Coloring::Trap(trap),
+            None => params.get_or("coloring", fractal.coloring)?,
+        };
+        fractal.cycle = positive(params, "cycle", fractal.cycle)?;
+        fractal.spread = params.get_or("spread", fractal.spread)?;
+        if let Some(interior) = params.get_with("interior", parse_hex)? {
+            fractal.interior = decode_srgb([interior.r, interior.g, interior.b]);
+        }
+        fractal.colormap = colormap(params)?;
+        Ok(Box::new(fractal))
+    }
+}
+
+/// Defines a list of parameters and a second one with a few more, so that
+/// generators sharing most of their parameters cannot drift apart.
+macro_rules! parameter_lists {
+    ($base:ident = [$($common:literal),* $(,)?], $extended:ident = [.., $($extra:literal),* $(,)?]) => {
+        const $base: &[&str] = &[$($common),*];
+        const $extended: &[&str] = &[$($common,)* $($extra),*];
+    };
+}
+
+parameter_lists!(
+    FRACTAL_PARAMETERS = [
+        "stops",
+        "colormap",
+        "interpolation",
+        "spread",
+        "center",
+        "zoom",
+        "iterations",
+        "bailout",
+        "precision",
+        "coloring",
+        "trap",
+        "cycle",
+        "interior",
+    ],
+    JULIA_PARAMETERS = [.., "c"]
+);
+
+parameter_lists!(
+    NOISE_PARAMETERS = [
+        "stops",
+        "colormap",
+        "interpolation",
+        "seed",
+        "scale",
+        "dimensions",
+        "period",
+        "fractal",
+        "octaves",
+        "lacunarity",
+        "gain",
+        "warp",
+    ],
+    WORLEY_PARAMETERS = [.., "metric", "feature"]
+);
+
+/// The camera given by the `position`, `look-at`, `up` and `fov` parameters, with
+/// the view of `scene` for any that are missing.
+fn camera(params: &Params, scene: &Scene) -> Result<Camera> {
+    let position = params.get_with("position", parse_vec3)?.unwrap_or(scene.position);
+    let look_at = params.get_with("look-at", parse_vec3)?.unwrap_or(scene.look_at);
+    let up = params.get_with("up", parse_vec3)?.unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+    let fov = params.get_with("fov", |v| match v.parse::<f64>() {
+        Ok(fov) if fov > 0.0 && fov < 180.0 => Ok(fov),
+        _ => Err("expected an angle between 0 and 180 degrees".to_string()),
+    })?;
+    Camera::new(position, look_at, up, fov.unwrap_or(scene.fov))
+}
+
+/// The sky given by the `horizon` and `zenith` colors, with those of `defaults`
+/// for any that are missing. The hex colors are decoded with `transfer`, the curve
+/// the output is encoded with by default, so that they come out as written.
+fn sky(params: &Params, defaults: Sky, transfer: Transfer) -> Result<Sky> {
+    let color = |key: &str, default| -> Result<_> {
+        Ok(params.get_with(key, parse_hex)?.map_or(default, |c| {
+            Color::new(transfer.decode(c.r), transfer.decode(c.g), transfer.decode(c.b))
+        }))
+    };
+    Ok(Sky {
+        horizon: color("horizon", defaults.horizon)?,
+        zenith: color("zenith", defaults.zenith)?,
+    })
+}
+
+/// Parses `key` as a positive number, falling back to `default` if it was not given.
+fn positive(params: &Params, key: &str, default: f64) -> Result<f64> {
+    let value = params.get_with(key, |v| match v.parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => Ok(v),
+        _ => Err("expected a positive number".to_string()),
+    })?;
+    Ok(value.unwrap_or(default))
+}
+
+/// The colormap given by either the `stops` or the `colormap` parameter, black to
+/// white if neither is.
+fn colormap(params: &Params) -> Result<Colormap> {
+    match (params.raw("colormap"), params.get_with("stops", parse_stops)?) {
+        (Some(_), Some(_)) => Err(Error::Usage("give either 'stops' or 'colormap', not both".to_string())),
+        (Some(_), None) if params.raw("interpolation").is_some() => Err(Error::Usage(
+            "'interpolation' applies to 'stops', not to a 'colormap'".to_string(),
+        )),
+        (Some(spec), None) => Colormap::from_spec(spec),
+        (None, stops) => {
+            let stops = stops.unwrap_or_else(|| parse_stops(DEFAULT_STOPS).unwrap());
+            Ok(Colormap::Ramp(Ramp::new(&stops, params.get_or("interpolation", Default::default())?)))
+        }
+    }
+}
+
+impl FromStr for Generator {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Generator::ALL
+            .iter()
+            .copied()
+            .find(|g| g.name() == s)
+            .ok_or_else(|| format!("unknown generator '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Quantizer, Renderer};
+
+    /// Renders `generator` and quantizes it with its default transfer function.
+    fn render_rgb8(generator: Generator, params: &Params, width: u32, height: u32) -> image::RgbImage {
+        let shader = generator.shader(params).unwrap();
+        let canvas = Renderer::new(width, height).render(&*shader).unwrap();
+        let quantizer = Quantizer {
+            transfer: generator.default_transfer(),
+            ..Quantizer::default()
+        };
+        canvas.to_rgb8(&quantizer)
+    }
+
+    #[test]
+    fn sky_colors_come_out_as_written() {
+        let mut params = Params::new();
+        params.set("horizon", "#808080");
+        params.set("zenith", "#808080");
+        assert!(render_rgb8(Generator::Sky, &params, 4, 3).pixels().all(|p| p.0 == [128, 128, 128]));
+
+        // Straight down is all horizon.
+        params.set("horizon", "#204060");
+        params.set("look-at", "0,-1,0");
+        params.set("up", "0,0,-1");
+        params.set("fov", "1");
+        let image = render_rgb8(Generator::Sky, &params, 1, 1);
+        assert_eq!(image.get_pixel(0, 0).0, [32, 64, 96]);
+    }
+
+    #[test]
+    fn scenes_are_encoded_as_srgb() {
+        assert_eq!(Generator::Scene.default_transfer(), Transfer::Srgb);
+        // Looking straight up past the objects, the sky keeps its written color
+        // through the linear path tracer.
+        let mut params = Params::new();
+        for (key, value) in [
+            ("zenith", "#204060"),
+            ("horizon", "#204060"),
+            ("position", "0,0,0"),
+            ("look-at", "0,1,0"),
+            ("up", "0,0,-1"),
+            ("fov", "1"),
+        ] {
+            params.set(key, value);
+        }
+        let image = render_rgb8(Generator::Scene, &params, 2, 2);
+        assert!(image.pixels().all(|p| p.0 == [32, 64, 96]), "{:?}", image.get_pixel(0, 0));
+    }
+
+    /// The message of the usage error `generator` fails with.
+    fn usage_error(generator: Generator, pairs: &[(&str, &str)]) -> String {
+        let mut params = Params::new();
+        for &(key, value) in pairs {
+            params.set(key, value);
+        }
+        match generator.shader(&params) {
+            Err(Error::Usage(message)) => message,
+            Err(e) => panic!("{:?}: {}", generator, e),
+            Ok(_) => panic!("{:?} accepted {:?}", generator, pairs),
+        }
+    }
+
+    #[test]
+    fn extended_parameter_lists_keep_the_base_ones() {
+        assert_eq!(JULIA_PARAMETERS[..FRACTAL_PARAMETERS.len()], *FRACTAL_PARAMETERS);
+        assert_eq!(JULIA_PARAMETERS[FRACTAL_PARAMETERS.len()..], ["c"]);
+        assert_eq!(WORLEY_PARAMETERS[..NOISE_PARAMETERS.len()], *NOISE_PARAMETERS);
+        assert_eq!(WORLEY_PARAMETERS[NOISE_PARAMETERS.len()..], ["metric", "feature"]);
+    }
+
+    #[test]
+    fn shaders_reject_unknown_and_out_of_range_parameters() {
+        // Only Julia sets take a constant, and only Worley noise a metric.
+        assert_eq!(
+            usage_error(Generator::Mandelbrot, &[("c", "0,0")]),
+            format!("unknown parameter 'c' for 'mandelbrot' (expected {})", FRACTAL_PARAMETERS.join(", "))
+        );
+        assert!(usage_error(Generator::Perlin, &[("metric", "manhattan")]).starts_with("unknown parameter 'metric'"));
+        assert_eq!(
+            usage_error(Generator::Gradient, &[("stops", "#000,#fff")]),
+            "'gradient' takes no parameters, but 'stops' was given"
+        );
+
+        assert_eq!(
+            usage_error(Generator::Perlin, &[("fractal", "fbm"), ("octaves", "17")]),
+            "invalid value '17' for parameter 'octaves': expected a whole number from 1 to 16"
+        );
+        assert_eq!(
+            usage_error(Generator::Sky, &[("fov", "180")]),
+            "invalid value '180' for parameter 'fov': expected an angle between 0 and 180 degrees"
+        );
+        assert_eq!(
+            usage_error(Generator::RadialGradient, &[("radius", "0")]),
+            "invalid value '0' for parameter 'radius': expected a positive number"
+        );
+    }
+
+    #[test]
+    fn colormaps_take_neither_stops_nor_interpolation() {
+        let mut params = Params::new();
+        params.set("colormap", "magma");
+        assert!(colormap(&params).is_ok());
+        params.set("interpolation", "oklab");
+        assert!(matches!(colormap(&params), Err(Error::Usage(_))));
+
+        let mut params = Params::new();
+        params.set("colormap", "magma");
+        params.set("stops", "#000000,#ffffff");
+        assert!(matches!(colormap(&params), Err(Error::Usage(_))));
+    }
+}
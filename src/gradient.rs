@@ -0,0 +1,551 @@
+//! Multi-stop color gradients.
+//!
+//! A [`Gradient`] maps each pixel to a position `t` along a [`Shape`], folds `t`
+//! into `0.0..=1.0` with a [`Spread`] mode and looks the color up in a
+//! [`Colormap`], usually a [`Ramp`] of color stops.
+
+use crate::color::Color;
+use crate::colormap::Colormap;
+use crate::dither::parse_hex;
+use crate::shader::{ShadeContext, Shader};
+use crate::transfer::Transfer;
+use std::f64::consts::TAU;
+use std::str::FromStr;
+
+/// A color at a position along a ramp. Colors are linear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    pub offset: f64,
+    pub color: Color,
+}
+
+/// The color space in which neighbouring stops are blended.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Interpolation {
+    /// Blend linear light. Physically right, but midpoints look bright.
+    LinearRgb,
+    /// Blend sRGB-encoded values, as CSS and most image editors do by default.
+    Srgb,
+    /// Blend in OKLab, which keeps perceived lightness and hue even.
+    #[default]
+    Oklab,
+    /// Blend hue, saturation and lightness, taking the shorter way around the hue
+    /// circle.
+    Hsl,
+}
+
+/// What happens beyond the two ends of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Spread {
+    /// Extend the end colors.
+    #[default]
+    Pad,
+    /// Start over from the first stop.
+    Repeat,
+    /// Run back and forth like a mirror.
+    Reflect,
+}
+
+impl Spread {
+    /// Folds any position into `0.0..=1.0`.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Spread::Pad => t.clamp(0.0, 1.0),
+            Spread::Repeat => t - t.floor(),
+            Spread::Reflect => 1.0 - ((t.rem_euclid(2.0)) - 1.0).abs(),
+        }
+    }
+}
+
+/// A sequence of color stops and the space they are blended in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ramp {
+    offsets: Vec<f64>,
+    /// The stop colors converted into the interpolation space.
+    coords: Vec<[f64; 3]>,
+    interpolation: Interpolation,
+}
+
+impl Ramp {
+    /// Creates a ramp from stops sorted by offset. Panics if `stops` is empty.
+    pub fn new(stops: &[Stop], interpolation: Interpolation) -> Ramp {
+        assert!(!stops.is_empty(), "a ramp needs at least one stop");
+        Ramp {
+            offsets: stops.iter().map(|s| s.offset).collect(),
+            coords: stops.iter().map(|s| to_space(interpolation, s.color)).collect(),
+            interpolation,
+        }
+    }
+
+    /// The color at position `t`. Positions before the first stop or after the last
+    /// take the color of that stop.
+    pub fn at(&self, t: f64) -> Color {
+        let last = self.offsets.len() - 1;
+        if t.is_nan() || t <= self.offsets[0] {
+            return from_space(self.interpolation, self.coords[0]);
+        }
+        if t >= self.offsets[last] {
+            return from_space(self.interpolation, self.coords[last]);
+        }
+
+        // The first stop past `t`. Stops sharing an offset make a hard edge.
+        let next = self.offsets.iter().position(|&o| o > t).unwrap_or(last);
+        let (a, b) = (next - 1, next);
+        let f = (t - self.offsets[a]) / (self.offsets[b] - self.offsets[a]);
+        let mixed = match self.interpolation {
+            Interpolation::Hsl => mix_hsl(self.coords[a], self.coords[b], f),
+            _ => mix(self.coords[a], self.coords[b], f),
+        };
+        from_space(self.interpolation, mixed)
+    }
+}
+
+fn mix(a: [f64; 3], b: [f64; 3], f: f64) -> [f64; 3] {
+    [a[0] + (b[0] - a[0]) * f, a[1] + (b[1] - a[1]) * f, a[2] + (b[2] - a[2]) * f]
+}
+
+fn mix_hsl(a: [f64; 3], b: [f64; 3], f: f64) -> [f64; 3] {
+    // Grays have no hue of their own and borrow the other end's.
+    let (mut ha, mut hb) = (a[0], b[0]);
+    if a[1] == 0.0 {
+        ha = hb;
+    }
+    if b[1] == 0.0 {
+        hb = ha;
+    }
+    let mut dh = hb - ha;
+    if dh > 0.5 {
+        dh -= 1.0;
+    } else if dh < -0.5 {
+        dh += 1.0;
+    }
+    let [_, s, l] = mix(a, b, f);
+    [(ha + dh * f).rem_euclid(1.0), s, l]
+}
+
+fn to_space(interpolation: Interpolation, c: Color) -> [f64; 3] {
+    match interpolation {
+        Interpolation::LinearRgb => [c.r, c.g, c.b],
+        Interpolation::Srgb => encode_srgb(c),
+        Interpolation::Oklab => linear_to_oklab(c),
+        Interpolation::Hsl => rgb_to_hsl(encode_srgb(c)),
+    }
+}
+
+fn from_space(interpolation: Interpolation, v: [f64; 3]) -> Color {
+    match interpolation {
+        Interpolation::LinearRgb => Color::new(v[0], v[1], v[2]),
+        Interpolation::Srgb => decode_srgb(v),
+        Interpolation::Oklab => oklab_to_linear(v),
+        Interpolation::Hsl => decode_srgb(hsl_to_rgb(v)),
+    }
+}
+
+fn encode_srgb(c: Color) -> [f64; 3] {
+    // The curve is only defined for non-negative values; mirror it for the rest.
+    let encode = |v: f64| v.signum() * Transfer::Srgb.encode(v.abs());
+    [encode(c.r), encode(c.g), encode(c.b)]
+}
+
+pub(crate) fn decode_srgb(v: [f64; 3]) -> Color {
+    let decode = |v: f64| v.signum() * Transfer::Srgb.decode(v.abs());
+    Color::new(decode(v[0]), decode(v[1]), decode(v[2]))
+}
+
+// Björn Ottosson's OKLab, from linear sRGB.
+fn linear_to_oklab(c: Color) -> [f64; 3] {
+    let l = 0.412_221_470_8 * c.r + 0.536_332_536_3 * c.g + 0.051_445_992_9 * c.b;
+    let m = 0.211_903_498_2 * c.r + 0.680_699_545_1 * c.g + 0.107_396_956_6 * c.b;
+    let s = 0.088_302_461_9 * c.r + 0.281_718_837_6 * c.g + 0.629_978_700_5 * c.b;
+    let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+    [
+        0.210_454_255_3 * l + 0.793_617_785_0 * m - 0.004_072_046_8 * s,
+        1.977_998_495_1 * l - 2.428_592_205_0 * m + 0.450_593_709_9 * s,
+        0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766_0 * s,
+    ]
+}
+
+fn oklab_to_linear([l, a, b]: [f64; 3]) -> Color {
+    let l_ = l + 0.396_337_777_4 * a + 0.215_803_757_3 * b;
+    let m_ = l - 0.105_561_345_8 * a - 0.063_854_172_8 * b;
+    let s_ = l - 0.089_484_177_5 * a - 1.291_485_548_0 * b;
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+    Color::new(
+        4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+        -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+        -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701_0 * s,
+    )
+}
+
+/// Hue in turns, saturation and lightness, from encoded RGB.
+fn rgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return [0.0, 0.0, l];
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    [h / 6.0, s, l]
+}
+
+fn hsl_to_rgb([h, s, l]: [f64; 3]) -> [f64; 3] {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(1.0) * 6.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    [r + m, g + m, b + m]
+}
+
+/// Parses a comma separated list of stops such as `#ff0000, #00ff00@0.3, #0000ff`.
+///
+/// Colors are sRGB hex values and are stored as linear light. Offsets are optional:
+/// as in CSS, the first and last stops default to 0 and 1, stops without an offset
+/// are spread evenly between their neighbours, and an offset smaller than an earlier
+/// one is raised to match it.
+pub fn parse_stops(s: &str) -> Result<Vec<Stop>, String> {
+    let mut colors = Vec::new();
+    let mut offsets = Vec::new();
+    for item in s.split(',') {
+        let (color, offset) = match item.trim().split_once('@') {
+            Some((color, offset)) => match offset.trim().parse::<f64>() {
+                Ok(o) if o.is_finite() => (color, Some(o)),
+                _ => return Err(format!("invalid stop offset '{}'", offset)),
+            },
+            None => (item.trim(), None),
+        };
+        let c = parse_hex(color.trim())?;
+        colors.push(decode_srgb([c.r, c.g, c.b]));
+        offsets.push(offset);
+    }
+
+    // A single stop is a solid color.
+    let n = colors.len();
+    if offsets[0].is_none() {
+        offsets[0] = Some(0.0);
+    }
+    if n > 1 && offsets[n - 1].is_none() {
+        offsets[n - 1] = Some(1.0);
+    }
+
+    let mut resolved = vec![0.0; n];
+    let mut max = f64::NEG_INFINITY;
+    let mut i = 0;
+    while i < n {
+        match offsets[i] {
+            Some(o) => {
+                max = max.max(o);
+                resolved[i] = max;
+                i += 1;
+            }
+            None => {
+                // Spread the run of missing offsets between the stops around it.
+                let start = i - 1;
+                let end = (i..n).find(|&j| offsets[j].is_some()).expect("the last stop has an offset");
+                let (from, to) = (resolved[start], offsets[end].unwrap().max(resolved[start]));
+                for (k, offset) in resolved.iter_mut().enumerate().take(end).skip(i) {
+                    *offset = from + (to - from) * (k - start) as f64 / (end - start) as f64;
+                }
+                i = end;
+            }
+        }
+    }
+
+    Ok(colors
+        .into_iter()
+        .zip(resolved)
+        .map(|(color, offset)| Stop { offset, color })
+        .collect())
+}
+
+/// How a pixel position is turned into a position along the gradient.
+///
+/// Centers are given in the normalized `u, v` coordinates of [`ShadeContext`], and
+/// angles in degrees clockwise from the positive x axis. Lengths are measured in
+/// pixels, so shapes stay round on non-square images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Parallel bands across the whole image. At angle 0 the gradient runs from the
+    /// left edge to the right; in general it runs between the two corners that are
+    /// furthest apart along its direction.
+    Linear { angle: f64 },
+    /// Circles around `center`. `radius` is a fraction of the shorter side, or the
+    /// distance to the farthest corner if `None`.
+    Radial { center: (f64, f64), radius: Option<f64> },
+    /// A sweep around `center`, starting at `angle`.
+    Conic { center: (f64, f64), angle: f64 },
+    /// Squares turned 45 degrees around `center`. `radius` is as for `Radial`.
+    Diamond { center: (f64, f64), radius: Option<f64> },
+}
+
+impl Shape {
+    /// The unspread gradient position at a pixel.
+    pub fn position(&self, ctx: &ShadeContext) -> f64 {
+        let (w, h) = ((ctx.width.max(2) - 1) as f64, (ctx.height.max(2) - 1) as f64);
+        let offset = |(cu, cv): (f64, f64)| (ctx.x - cu * w, ctx.y - cv * h);
+        // The distance from the center to the farthest corner under `metric`.
+        let farthest = |(cu, cv): (f64, f64), metric: fn(f64, f64) -> f64| {
+            let (dx, dy) = ((cu * w).max(w - cu * w), (cv * h).max(h - cv * h));
+            metric(dx, dy)
+        };
+        let shorter = w.min(h).max(1.0);
+
+        match *self {
+            Shape::Linear { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let length = (w * cos).abs() + (h * sin).abs();
+                let (dx, dy) = offset((0.5, 0.5));
+                0.5 + (dx * cos + dy * sin) / length.max(f64::EPSILON)
+            }
+            Shape::Radial { center, radius } => {
+                let (dx, dy) = offset(center);
+                let r = radius.map_or_else(|| farthest(center, f64::hypot), |r| r * shorter);
+                dx.hypot(dy) / r.max(f64::EPSILON)
+            }
+            Shape::Conic { center, angle } => {
+                let (dx, dy) = offset(center);
+                ((dy.atan2(dx) - angle.to_radians()) / TAU).rem_euclid(1.0)
+            }
+            Shape::Diamond { center, radius } => {
+                let (dx, dy) = offset(center);
+                let r = radius.map_or_else(|| farthest(center, |x, y| x + y), |r| r * shorter);
+                (dx.abs() + dy.abs()) / r.max(f64::EPSILON)
+            }
+        }
+    }
+}
+
+/// A gradient shader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub shape: Shape,
+    pub colormap: Colormap,
+    pub spread: Spread,
+}
+
+impl Shader for Gradient {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        self.colormap.at(self.spread.apply(self.shape.position(ctx)))
+    }
+}
+
+impl FromStr for Interpolation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Interpolation::LinearRgb),
+            "srgb" => Ok(Interpolation::Srgb),
+            "oklab" => Ok(Interpolation::Oklab),
+            "hsl" => Ok(Interpolation::Hsl),
+            _ => Err(format!(
+                "unknown interpolation '{}' (expected linear, srgb, oklab or hsl)",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for Spread {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pad" => Ok(Spread::Pad),
+            "repeat" => Ok(Spread::Repeat),
+            "reflect" => Ok(Spread::Reflect),
+            _ => Err(format!("unknown spread mode '{}' (expected pad, repeat or reflect)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generator, Params, Quantizer, Renderer};
+
+    #[test]
+    fn stops_come_out_as_given_with_the_default_transfer() {
+        let generator = Generator::LinearGradient;
+        let quantizer = Quantizer {
+            transfer: generator.default_transfer(),
+            ..Quantizer::default()
+        };
+        for &(stop, rgb) in &[("#808080", [128, 128, 128]), ("#ff8000", [255, 128, 0]), ("#204060", [32, 64, 96])] {
+            // A single stop fills the image.
+            let mut params = Params::new();
+            params.set("stops", stop);
+            let shader = generator.shader(&params).unwrap();
+            let canvas = Renderer::new(3, 2).render(&*shader).unwrap();
+            let image = canvas.to_rgb8(&quantizer);
+            assert!(image.pixels().all(|p| p.0 == rgb), "{}", stop);
+        }
+    }
+
+    #[test]
+    fn offsets_are_filled_in_like_css() {
+        let offsets = |s| parse_stops(s).unwrap().iter().map(|stop| stop.offset).collect::<Vec<_>>();
+        assert_eq!(offsets("#000, #fff"), [0.0, 1.0]);
+        assert_eq!(offsets("#000, #888, #aaa, #fff@0.9"), [0.0, 0.3, 0.6, 0.9]);
+        assert_eq!(offsets("#000@0.5, #fff@0.2"), [0.5, 0.5]);
+        assert!(parse_stops("#000@x").is_err());
+    }
+
+    fn assert_near(actual: f64, expected: f64, what: &str) {
+        assert!((actual - expected).abs() < 1e-12, "{}: {} instead of {}", what, actual, expected);
+    }
+
+    /// The color halfway between two linear colors.
+    fn midpoint(from: Color, to: Color, interpolation: Interpolation) -> Color {
+        let stops = [Stop { offset: 0.0, color: from }, Stop { offset: 1.0, color: to }];
+        Ramp::new(&stops, interpolation).at(0.5)
+    }
+
+    fn assert_color(actual: Color, expected: [f64; 3], what: &str) {
+        for (&a, &e) in [actual.r, actual.g, actual.b].iter().zip(&expected) {
+            assert!((a - e).abs() < 1e-6, "{}: {:?} instead of {:?}", what, actual, expected);
+        }
+    }
+
+    #[test]
+    fn interpolation_spaces_blend_red_and_blue_differently() {
+        let (red, blue) = (Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+        assert_color(midpoint(red, blue, Interpolation::LinearRgb), [0.5, 0.0, 0.5], "linear");
+        // Half of the encoded value, decoded again.
+        let half = Transfer::Srgb.decode(0.5);
+        assert_color(midpoint(red, blue, Interpolation::Srgb), [half, 0.0, half], "srgb");
+
+        // Halfway between Ottosson's published coordinates for red and blue.
+        let oklab = linear_to_oklab(midpoint(red, blue, Interpolation::Oklab));
+        let expected = [(0.627_955 + 0.452_014) / 2.0, (0.224_863 - 0.032_457) / 2.0, (0.125_846 - 0.311_528) / 2.0];
+        for (&actual, &expected) in oklab.iter().zip(&expected) {
+            assert!((actual - expected).abs() < 1e-5, "oklab: {:?} instead of {:?}", oklab, expected);
+        }
+
+        // Hue takes the shorter way: from red at 0 back past 1 to blue at 2/3, through
+        // magenta rather than green.
+        assert_color(midpoint(red, blue, Interpolation::Hsl), [1.0, 0.0, 1.0], "hsl");
+        // And from magenta to yellow through red rather than cyan.
+        let (magenta, yellow) = (Color::new(1.0, 0.0, 1.0), Color::new(1.0, 1.0, 0.0));
+        assert_color(midpoint(magenta, yellow, Interpolation::Hsl), [1.0, 0.0, 0.0], "hsl wrap");
+        // A gray end takes the other end's hue, so only saturation changes: half
+        // saturated red at lightness 0.5 is (0.75, 0.25, 0.25) encoded.
+        let gray = Color::gray(half);
+        let (high, low) = (Transfer::Srgb.decode(0.75), Transfer::Srgb.decode(0.25));
+        assert_color(midpoint(gray, red, Interpolation::Hsl), [high, low, low], "hsl gray");
+    }
+
+    /// The gradient position of pixel `(x, y)` in an 11x11 image, so that pixel
+    /// centers run from 0 to 10.
+    fn position(shape: Shape, x: f64, y: f64) -> f64 {
+        shape.position(&ShadeContext::new(x, y, 11, 11))
+    }
+
+    #[test]
+    fn linear_gradients_run_between_opposite_edges_or_corners() {
+        let across = Shape::Linear { angle: 0.0 };
+        for y in [0.0, 3.0, 10.0] {
+            assert_near(position(across, 0.0, y), 0.0, "left edge");
+            assert_near(position(across, 5.0, y), 0.5, "middle");
+            assert_near(position(across, 10.0, y), 1.0, "right edge");
+        }
+        let down = Shape::Linear { angle: 90.0 };
+        assert_near(position(down, 7.0, 0.0), 0.0, "top edge");
+        assert_near(position(down, 7.0, 10.0), 1.0, "bottom edge");
+        let diagonal = Shape::Linear { angle: 45.0 };
+        assert_near(position(diagonal, 0.0, 0.0), 0.0, "top left");
+        assert_near(position(diagonal, 10.0, 10.0), 1.0, "bottom right");
+        assert_near(position(diagonal, 10.0, 0.0), 0.5, "top right");
+    }
+
+    #[test]
+    fn radial_and_diamond_gradients_grow_from_their_center() {
+        let center = (0.5, 0.5);
+        let radial = Shape::Radial { center, radius: None };
+        assert_near(position(radial, 5.0, 5.0), 0.0, "center");
+        assert_near(position(radial, 0.0, 0.0), 1.0, "corner");
+        assert_near(position(radial, 10.0, 5.0), 0.5f64.sqrt(), "edge");
+        let circle = Shape::Radial { center, radius: Some(0.5) };
+        assert_near(position(circle, 10.0, 5.0), 1.0, "right");
+        assert_near(position(circle, 5.0, 0.0), 1.0, "top");
+        assert_near(position(circle, 8.0, 9.0), 1.0, "3-4-5");
+
+        let diamond = Shape::Diamond { center, radius: None };
+        assert_near(position(diamond, 5.0, 5.0), 0.0, "center");
+        assert_near(position(diamond, 10.0, 10.0), 1.0, "corner");
+        assert_near(position(diamond, 10.0, 5.0), 0.5, "edge");
+        let square = Shape::Diamond { center, radius: Some(0.5) };
+        assert_near(position(square, 10.0, 5.0), 1.0, "right");
+        assert_near(position(square, 7.0, 3.0), 0.8, "inside");
+        // Off-center shapes measure to the farthest corner.
+        let corner = Shape::Radial { center: (0.0, 0.0), radius: None };
+        assert_near(position(corner, 10.0, 10.0), 1.0, "opposite corner");
+    }
+
+    #[test]
+    fn conic_gradients_sweep_clockwise_from_their_angle() {
+        let center = (0.5, 0.5);
+        let conic = Shape::Conic { center, angle: 0.0 };
+        // y grows downwards, so a quarter turn clockwise points down.
+        assert_near(position(conic, 10.0, 5.0), 0.0, "right");
+        assert_near(position(conic, 5.0, 10.0), 0.25, "down");
+        assert_near(position(conic, 0.0, 5.0), 0.5, "left");
+        assert_near(position(conic, 5.0, 0.0), 0.75, "up");
+        let turned = Shape::Conic { center, angle: 90.0 };
+        assert_near(position(turned, 5.0, 10.0), 0.0, "down");
+        assert_near(position(turned, 10.0, 5.0), 0.75, "right");
+    }
+
+    #[test]
+    fn spread_modes_fold_positions_outside_the_gradient() {
+        let cases = [
+            (-0.5, [0.0, 0.5, 0.5]),
+            (-0.25, [0.0, 0.75, 0.25]),
+            (0.3, [0.3, 0.3, 0.3]),
+            (1.0, [1.0, 0.0, 1.0]),
+            (1.25, [1.0, 0.25, 0.75]),
+            (2.0, [1.0, 0.0, 0.0]),
+            (2.25, [1.0, 0.25, 0.25]),
+        ];
+        for &(t, expected) in &cases {
+            for (spread, expected) in [Spread::Pad, Spread::Repeat, Spread::Reflect].iter().zip(expected) {
+                assert_near(spread.apply(t), expected, &format!("{:?} at {}", spread, t));
+            }
+        }
+
+        // Through a gradient: a circle of radius 4 pixels reaches 1.25 at the edge of
+        // the image, 5 pixels from its center.
+        let ramp = Ramp::new(
+            &[
+                Stop { offset: 0.0, color: Color::BLACK },
+                Stop { offset: 1.0, color: Color::WHITE },
+            ],
+            Interpolation::LinearRgb,
+        );
+        for (spread, gray) in [(Spread::Pad, 1.0), (Spread::Repeat, 0.25), (Spread::Reflect, 0.75)] {
+            let gradient = Gradient {
+                shape: Shape::Radial { center: (0.5, 0.5), radius: Some(0.4) },
+                colormap: Colormap::Ramp(ramp.clone()),
+                spread,
+            };
+            let color = gradient.shade(&ShadeContext::new(10.0, 5.0, 11, 11));
+            assert_near(color.g, gray, &format!("{:?}", spread));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Objects that rays can hit.
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// Where a ray hit a surface.
+#[derive(Debug, Clone, Copy)]
+pub struct HitRecord<'a> {
+    /// The ray parameter at the hit, so that `point == ray.at(t)`.
+    pub t: f64,
+    pub point: Point3,
+    /// The unit surface normal, facing against the ray.
+    pub normal: Vec3,
+    /// Whether the ray hit the outside of the surface.
+    pub front_face: bool,
+    /// Surface coordinates for texturing, nominally in `0.0..=1.0`.
+    pub uv: (f64, f64),
+    /// What the surface is made of, if the object was given a material.
+    pub material: Option<&'a dyn Material>,
+}
+
+impl<'a> HitRecord<'a> {
+    /// A hit at `t` along `ray`. `outward_normal` must have unit length and point
+    /// out of the object; the stored normal is flipped to face the ray.
+    pub fn new(ray: &Ray, t: f64, outward_normal: Vec3, uv: (f64, f64)) -> HitRecord<'a> {
+        let front_face = ray.direction.dot(outward_normal) < 0.0;
+        HitRecord {
+            t,
+            point: ray.at(t),
+            normal: if front_face { outward_normal } else { -outward_normal },
+            front_face,
+            uv,
+            material: None,
+        }
+    }
+}
+
+/// Anything a ray can intersect.
+///
+/// Like shaders, objects are shared between render threads.
+pub trait Hittable: Send + Sync {
+    /// The nearest hit with `t_min < t < t_max`, if any.
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// A box around the whole object, or `None` if it is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+impl<H: Hittable + ?Sized> Hittable for Box<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+}
+
+/// A group of objects, hit wherever the nearest of them is.
+#[derive(Default)]
+pub struct HittableList {
+    pub objects: Vec<Box<dyn Hittable>>,
+}
+
+impl HittableList {
+    pub fn new() -> HittableList {
+        HittableList::default()
+    }
+
+    pub fn add<H: Hittable + 'static>(&mut self, object: H) {
+        self.objects.push(Box::new(object));
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for object in &self.objects {
+            if let Some(hit) = object.hit(ray, t_min, t_max) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.objects.iter().map(|object| object.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |all, aabb| Some(all.union(aabb?)))
+    }
+}
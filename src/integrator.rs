@@ -0,0 +1,114 @@
+//! Path tracing: following light backwards from the camera through the scene.
+
+use crate::color::Color;
+use crate::hittable::Hittable;
+use crate::material::Lambertian;
+use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::sky::Sky;
+
+/// What objects without a material are made of.
+static DEFAULT_MATERIAL: Lambertian = Lambertian {
+    albedo: Color::gray(0.5),
+};
+
+/// Estimates the light arriving along a ray by following a single random path
+/// through the scene until it escapes to the sky or is absorbed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathTracer {
+    /// The most surfaces a path can bounce off. Longer paths are cut off, and
+    /// contribute no more light.
+    pub max_depth: u32,
+    /// How many bounces every path gets before Russian roulette may end it. Paths
+    /// that survive are brightened to make up for the ones that do not, so the
+    /// image stays unbiased while dim paths stop early.
+    pub roulette_depth: u32,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer {
+            max_depth: 50,
+            roulette_depth: 5,
+        }
+    }
+}
+
+impl PathTracer {
+    /// The light arriving at the origin of `ray` from the direction it points in.
+    pub fn radiance(&self, world: &dyn Hittable, sky: &Sky, mut ray: Ray, rng: &mut Rng) -> Color {
+        let (mut light, mut throughput) = (Color::BLACK, Color::WHITE);
+        for depth in 0..self.max_depth {
+            let hit = match world.hit(&ray, 1e-3, f64::INFINITY) {
+                Some(hit) => hit,
+                None => return light + throughput * sky.color(&ray),
+            };
+            let material = hit.material.unwrap_or(&DEFAULT_MATERIAL);
+            light += throughput * material.emitted(&hit);
+            let scatter = match material.scatter(&ray, &hit, rng) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            throughput = throughput * scatter.attenuation;
+            ray = scatter.ray;
+
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.max_channel().min(0.95);
+                if rng.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        light
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{HitRecord, HittableList};
+    use crate::material::{DiffuseLight, Material, Scatter, Surface};
+    use crate::shapes::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn roulette_keeps_the_average_unchanged() {
+        // Inside a closed sphere that reflects 80% of the light and emits 0.2,
+        // every path gathers 0.2 * (1 + 0.8 + 0.8² + ...) = 1 on average.
+        #[derive(Debug)]
+        struct Glowing;
+        impl Material for Glowing {
+            fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter> {
+                Lambertian::new(Color::gray(0.8)).scatter(ray, hit, rng)
+            }
+
+            fn emitted(&self, _hit: &HitRecord) -> Color {
+                Color::gray(0.2)
+            }
+        }
+
+        let mut world = HittableList::new();
+        world.add(Surface::new(Sphere::new(Point3::ZERO, 1.0), Glowing));
+        let tracer = PathTracer {
+            max_depth: 1000,
+            roulette_depth: 1,
+        };
+        let mut rng = Rng::new(1);
+        let ray = Ray::new(Point3::ZERO, Vec3::new(0.0, 0.0, 1.0));
+        let paths = 20000;
+        let mean = (0..paths)
+            .map(|_| tracer.radiance(&world, &Sky::default(), ray, &mut rng).r)
+            .sum::<f64>()
+            / paths as f64;
+        assert!((mean - 1.0).abs() < 0.03, "mean radiance {}", mean);
+
+        // A light seen directly is its own color, and the sky is seen past it.
+        let mut world = HittableList::new();
+        world.add(Surface::new(Sphere::new(Point3::new(0.0, 0.0, 2.0), 1.0), DiffuseLight::new(Color::gray(4.0))));
+        let light = tracer.radiance(&world, &Sky::default(), ray, &mut rng);
+        assert_eq!(light, Color::gray(4.0));
+        let up = Ray::new(Point3::ZERO, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(tracer.radiance(&world, &Sky::default(), up, &mut rng), Sky::default().zenith);
+    }
+}
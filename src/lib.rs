@@ -0,0 +1,83 @@
+//! Renders generated images into an in-memory canvas and saves them to disk.
+//!
+//! The `output-image` binary is a thin command line wrapper around this crate:
+//!
+//! ```no_run
+//! use output_image::{Generator, Quantizer};
+//!
+//! let canvas = output_image::render(Generator::Gradient, 256, 256);
+//! canvas.save("image.png", &Quantizer::default()).unwrap();
+//! ```
+//!
+//! Anything implementing [`Shader`], including closures, can be rendered:
+//!
+//! ```
+//! use output_image::{Color, Renderer, ShadeContext};
+//!
+//! let stripes = |ctx: &ShadeContext| Color::gray((ctx.x as u32 / 8 % 2) as f64);
+//! let canvas = Renderer::new(64, 64).render(&stripes).unwrap();
+//! assert_eq!(canvas.pixel(8, 0), Color::WHITE);
+//! ```
+
+pub mod aabb;
+pub mod atomic;
+pub mod bvh;
+pub mod camera;
+pub mod canvas;
+pub mod color;
+pub mod colormap;
+pub mod dither;
+pub mod error;
+pub mod expr;
+pub mod exr;
+pub mod format;
+pub mod fractal;
+pub mod generator;
+pub mod gradient;
+pub mod hittable;
+pub mod integrator;
+pub mod lut;
+pub mod material;
+pub mod mesh;
+pub mod netpbm;
+pub mod noise;
+pub mod params;
+pub mod pfm;
+pub mod png;
+pub mod progress;
+pub mod quantize;
+pub mod radiance;
+pub mod ray;
+pub mod render;
+pub mod rng;
+pub mod sampling;
+pub mod scene;
+pub mod shader;
+pub mod shapes;
+pub mod sky;
+pub mod transfer;
+pub mod vec3;
+pub mod volume;
+
+pub use atomic::{AtomicFile, Overwrite};
+pub use camera::Camera;
+pub use canvas::Canvas;
+pub use color::Color;
+pub use colormap::Colormap;
+pub use error::{Error, Result};
+pub use format::OutputFormat;
+pub use generator::Generator;
+pub use params::Params;
+pub use quantize::Quantizer;
+pub use ray::Ray;
+pub use render::Renderer;
+pub use shader::{ShadeContext, Shader};
+pub use transfer::Transfer;
+pub use vec3::{Point3, Vec3};
+
+/// Renders `generator` with its default parameters into a new canvas of the given
+/// size.
+pub fn render(generator: Generator, width: u32, height: u32) -> Canvas {
+    let shader = generator.shader(&Params::new()).expect("default parameters are valid");
+    Renderer::new(width, height).render(&*shader).expect("the shared thread pool is always available")
+}
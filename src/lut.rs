@@ -0,0 +1,278 @@
+//! Color lookup tables in the Adobe / Resolve `.cube` format.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::transfer::Transfer;
+use std::fs;
+use std::path::Path;
+
+/// A `.cube` lookup table: either three 1D curves, one per channel, or a 3D
+/// lattice that maps any color to any other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    /// `true` for a 3D lattice, `false` for per-channel curves.
+    pub is_3d: bool,
+    /// Entries per axis.
+    pub size: usize,
+    /// The input values mapped to the first and last entry of each axis.
+    pub domain_min: [f64; 3],
+    pub domain_max: [f64; 3],
+    /// Output colors, with red varying fastest in a 3D table.
+    pub table: Vec<[f64; 3]>,
+}
+
+impl CubeLut {
+    /// Loads a `.cube` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<CubeLut> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| Error::from(e).with_path(path))?;
+        parse(&text).map_err(|(line, message)| Error::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        })
+    }
+
+    /// Looks up a color, interpolating between entries. Inputs outside the domain
+    /// take the value at its edge.
+    pub fn lookup(&self, c: [f64; 3]) -> [f64; 3] {
+        let last = (self.size - 1) as f64;
+        let mut index = [0usize; 3];
+        let mut frac = [0.0; 3];
+        for i in 0..3 {
+            let t = (c[i] - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i]);
+            let x = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) * last };
+            index[i] = (x.floor() as usize).min(self.size - 2);
+            frac[i] = x - index[i] as f64;
+        }
+
+        if !self.is_3d {
+            return [0, 1, 2].map(|i| {
+                let (a, b) = (self.table[index[i]][i], self.table[index[i] + 1][i]);
+                a + (b - a) * frac[i]
+            });
+        }
+
+        // Trilinear interpolation between the eight surrounding entries.
+        let n = self.size;
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let (dr, dg, db) = (corner & 1, corner >> 1 & 1, corner >> 2 & 1);
+            let weight = [dr, dg, db]
+                .iter()
+                .zip(frac)
+                .map(|(&d, f)| if d == 1 { f } else { 1.0 - f })
+                .product::<f64>();
+            let entry = self.table[(index[0] + dr) + (index[1] + dg) * n + (index[2] + db) * n * n];
+            for i in 0..3 {
+                out[i] += weight * entry[i];
+            }
+        }
+        out
+    }
+
+    /// Runs every pixel of `canvas` through the table.
+    ///
+    /// Most tables expect display-encoded input, so colors are encoded with
+    /// `transfer` before the lookup and decoded again afterwards. With the default
+    /// pass-through transfer the table sees the canvas values as they are. Values
+    /// outside the table's domain are clamped to it by [`lookup`](CubeLut::lookup).
+    pub fn apply(&self, canvas: &mut Canvas, transfer: Transfer) {
+        // The curves are mirrored for negative values, which some domains include.
+        let encode = |v: f64| v.signum() * transfer.encode(v.abs());
+        let decode = |v: f64| v.signum() * transfer.decode(v.abs());
+        canvas.map(|c| {
+            let [r, g, b] = self.lookup([encode(c.r), encode(c.g), encode(c.b)]);
+            Color::new(decode(r), decode(g), decode(b))
+        });
+    }
+}
+
+fn parse(text: &str) -> std::result::Result<CubeLut, (usize, String)> {
+    let mut lut = CubeLut {
+        title: None,
+        is_3d: true,
+        size: 0,
+        domain_min: [0.0; 3],
+        domain_max: [1.0; 3],
+        table: Vec::new(),
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap();
+        let rest: Vec<&str> = words.collect();
+        let floats = |count: usize| -> std::result::Result<Vec<f64>, (usize, String)> {
+            let values: Option<Vec<f64>> = rest.iter().map(|w| w.parse().ok().filter(|v: &f64| v.is_finite())).collect();
+            match values {
+                Some(values) if values.len() == count => Ok(values),
+                _ => Err((number, format!("expected {} numbers after {}", count, keyword))),
+            }
+        };
+
+        match keyword {
+            "TITLE" => lut.title = Some(line["TITLE".len()..].trim().trim_matches('"').to_string()),
+            "LUT_1D_SIZE" | "LUT_3D_SIZE" => {
+                if lut.size != 0 {
+                    return Err((number, "the table size is given twice".to_string()));
+                }
+                let is_3d = keyword == "LUT_3D_SIZE";
+                let max = if is_3d { 256 } else { 65536 };
+                lut.size = match rest.as_slice() {
+                    [n] => match n.parse::<usize>() {
+                        Ok(n) if (2..=max).contains(&n) => n,
+                        _ => return Err((number, format!("{} must be from 2 to {}", keyword, max))),
+                    },
+                    _ => return Err((number, format!("expected one number after {}", keyword))),
+                };
+                lut.is_3d = is_3d;
+            }
+            "DOMAIN_MIN" => lut.domain_min = to_triple(&floats(3)?),
+            "DOMAIN_MAX" => lut.domain_max = to_triple(&floats(3)?),
+            // Resolve writes the domain as a single range for all channels.
+            "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                let range = floats(2)?;
+                lut.domain_min = [range[0]; 3];
+                lut.domain_max = [range[1]; 3];
+            }
+            _ if keyword.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                if lut.size == 0 {
+                    return Err((number, "table data before LUT_1D_SIZE or LUT_3D_SIZE".to_string()));
+                }
+                let values: Option<Vec<f64>> = line
+                    .split_whitespace()
+                    .map(|w| w.parse().ok().filter(|v: &f64| v.is_finite()))
+                    .collect();
+                match values {
+                    Some(values) if values.len() == 3 => lut.table.push(to_triple(&values)),
+                    _ => return Err((number, format!("expected three numbers, found '{}'", line))),
+                }
+            }
+            _ => return Err((number, format!("unknown keyword '{}'", keyword))),
+        }
+    }
+
+    if lut.size == 0 {
+        return Err((0, "missing LUT_1D_SIZE or LUT_3D_SIZE".to_string()));
+    }
+    let expected = if lut.is_3d { lut.size.pow(3) } else { lut.size };
+    if lut.table.len() != expected {
+        return Err((0, format!("expected {} table entries, found {}", expected, lut.table.len())));
+    }
+    if (0..3).any(|i| lut.domain_max[i] <= lut.domain_min[i]) {
+        return Err((0, "DOMAIN_MAX must be above DOMAIN_MIN".to_string()));
+    }
+    Ok(lut)
+}
+
+fn to_triple(values: &[f64]) -> [f64; 3] {
+    [values[0], values[1], values[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3D table that maps every color to itself.
+    fn identity_3d(size: usize) -> String {
+        let mut text = format!("TITLE \"identity\"\nLUT_3D_SIZE {}\n", size);
+        let last = (size - 1) as f64;
+        for i in 0..size.pow(3) {
+            let (r, g, b) = (i % size, i / size % size, i / (size * size));
+            text += &format!("{} {} {}\n", r as f64 / last, g as f64 / last, b as f64 / last);
+        }
+        text
+    }
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-12), "{:?} vs {:?}", a, b);
+    }
+
+    #[test]
+    fn identity_tables_leave_colors_unchanged() {
+        let lut = parse(&identity_3d(5)).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("identity"));
+        assert!(lut.is_3d);
+        assert_eq!((lut.size, lut.table.len()), (5, 125));
+
+        let mut canvas = Canvas::new(7, 5);
+        canvas.fill(|x, y| Color::new(x as f64 / 6.0, y as f64 / 4.0, 0.3));
+        let original = canvas.clone();
+        for &transfer in &[Transfer::PassThrough, Transfer::Srgb, Transfer::Gamma(2.2)] {
+            let mut mapped = original.clone();
+            lut.apply(&mut mapped, transfer);
+            for (a, b) in mapped.pixels().iter().zip(original.pixels()) {
+                assert_close([a.r, a.g, a.b], [b.r, b.g, b.b]);
+            }
+        }
+
+        let curves = parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").unwrap();
+        assert!(!curves.is_3d);
+        assert_close(curves.lookup([0.25, 0.5, 0.75]), [0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn lookups_interpolate_between_entries() {
+        // Red is squared at the lattice points, green inverted and blue fixed at 0.5.
+        let mut text = "LUT_3D_SIZE 3\n".to_string();
+        for i in 0..27 {
+            let (r, g) = ((i % 3) as f64 / 2.0, (i / 3 % 3) as f64 / 2.0);
+            text += &format!("{} {} 0.5\n", r * r, 1.0 - g);
+        }
+        let lut = parse(&text).unwrap();
+        assert_close(lut.lookup([0.5, 0.5, 0.5]), [0.25, 0.5, 0.5]);
+        // Halfway between 0.5 and 1 on red, a quarter of the way on green.
+        assert_close(lut.lookup([0.75, 0.125, 0.9]), [0.625, 0.875, 0.5]);
+
+        let curves = parse("LUT_1D_SIZE 3\n0 1 0\n0.5 0 0\n1 1 0\n").unwrap();
+        assert_close(curves.lookup([0.25, 0.75, 0.0]), [0.25, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn inputs_are_clamped_to_the_domain() {
+        let lut = parse("LUT_1D_SIZE 2\nDOMAIN_MIN -1 0 0\nDOMAIN_MAX 1 4 1\n0 0 0\n1 1 1\n").unwrap();
+        assert_eq!((lut.domain_min, lut.domain_max), ([-1.0, 0.0, 0.0], [1.0, 4.0, 1.0]));
+        assert_close(lut.lookup([0.0, 2.0, 0.5]), [0.5, 0.5, 0.5]);
+        assert_close(lut.lookup([-3.0, 9.0, f64::NAN]), [0.0, 1.0, 0.0]);
+
+        // apply leaves the clamping to the table, so the domain takes effect.
+        let mut canvas = Canvas::new(1, 1);
+        canvas.fill(|_, _| Color::new(-0.5, 3.0, 2.0));
+        lut.apply(&mut canvas, Transfer::PassThrough);
+        let c = canvas.pixel(0, 0);
+        assert_close([c.r, c.g, c.b], [0.25, 0.75, 1.0]);
+
+        let ranged = parse(&format!("LUT_3D_INPUT_RANGE 0 2\n{}", identity_3d(2))).unwrap();
+        assert_close(ranged.lookup([1.0, 2.0, 0.0]), [0.5, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn malformed_files_report_the_offending_line() {
+        let line = |text: &str| parse(text).unwrap_err().0;
+        assert_eq!(line("# comment\nLUT_3D_SIZE 1\n"), 2);
+        assert_eq!(line("LUT_1D_SIZE 2\nLUT_3D_SIZE 2\n"), 2);
+        assert_eq!(line("0 0 0\n"), 1);
+        assert_eq!(line("LUT_1D_SIZE 2\n0 0 0\n1 1\n"), 3);
+        assert_eq!(line("LUT_1D_SIZE 2\n0 0 0\n\n1 -inf 1\n"), 4);
+        assert_eq!(line("LUT_1D_SIZE 2\n0 0 0\n1 NaN 1\n"), 3);
+        assert_eq!(line("LUT_1D_SIZE 2\nDOMAIN_MIN 0 0\n"), 2);
+        assert_eq!(line("LUT_1D_SIZE 2\nSHAPER x\n"), 2);
+        // Problems with the file as a whole are not on any one line.
+        assert_eq!(line("LUT_1D_SIZE 2\n0 0 0\n"), 0);
+        assert_eq!(line("TITLE \"empty\"\n"), 0);
+        assert_eq!(line("LUT_1D_SIZE 2\nDOMAIN_MAX 0 1 1\n0 0 0\n1 1 1\n"), 0);
+
+        // Files that cannot be read at all are named too.
+        let path = std::env::temp_dir().join("output-image-missing.cube");
+        let error = CubeLut::load(&path).unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+        assert!(error.to_string().starts_with(&format!("{}: ", path.display())), "{}", error);
+    }
+}
@@ -1,26 +1,91 @@
-use image::{RgbImage, ImageBuffer, Rgb};
+use output_image::lut::CubeLut;
+use output_image::{Colormap, Error, Generator, Renderer, Result};
+use std::io;
+use std::process::ExitCode;
 
-fn main() {
+mod cli;
 
-    const IMAGE_WIDTH: u32 = 256;
-    const IMAGE_HEIGHT: u32 = 256;
+use cli::{Command, RenderOptions};
 
-    let mut buffer: RgbImage = ImageBuffer::new(IMAGE_WIDTH, IMAGE_HEIGHT);
-
-    for (x, y, pixel) in buffer.enumerate_pixels_mut(){
-        let r = x as f64 / (IMAGE_WIDTH-1) as f64;
-        let g = y as f64 / (IMAGE_HEIGHT-1) as f64;
-        let b = 0.25;
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            if let Error::Usage(_) = e {
+                eprintln!("Run 'output-image --help' for usage.");
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
 
-        let ir = (255.999 * r) as u8;
-        let ig = (255.999 * g) as u8;
-        let ib = (255.999 * b) as u8;
+fn run() -> Result<()> {
+    match cli::parse(std::env::args().skip(1))? {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::List => {
+            for generator in Generator::ALL {
+                println!("{:<18}{}", generator.name(), generator.description());
+                if !generator.parameters().is_empty() {
+                    println!("{:<18}  -p {}", "", generator.parameters().join(", "));
+                }
+            }
+        }
+        Command::Render(options) => render(&options)?,
+    }
+    Ok(())
+}
 
-        *pixel = Rgb([ir, ig, ib]);
+fn render(options: &RenderOptions) -> Result<()> {
+    let to_stdout = options.output.as_os_str() == "-";
+    if !to_stdout {
+        // Refuse before rendering rather than after.
+        options.overwrite.check(&options.output)?;
     }
 
-    match buffer.save("image.png") {
-        Err(e) => eprintln!("Error writing file: {}", e),
-        Ok(()) => println!("Done."),
+    let renderer = Renderer {
+        time: options.time,
+        threads: options.threads,
+        sampling: options.sampling,
+        ..Renderer::new(options.width, options.height)
     };
-}
\ No newline at end of file
+    let shader = options.generator.shader(&options.params)?;
+    let colormap = options
+        .colormap
+        .as_deref()
+        .map(Colormap::from_spec)
+        .transpose()?;
+    let lut = options.lut.as_ref().map(CubeLut::load).transpose()?;
+
+    let progress = options.progress_style().reporter();
+    let mut canvas = renderer.render_with_progress(&*shader, &*progress)?;
+    if let Some(colormap) = colormap {
+        let (min, max) = options.colormap_range;
+        colormap.apply(&mut canvas, min, max);
+    }
+    if let Some(lut) = lut {
+        lut.apply(&mut canvas, options.quantizer.transfer);
+    }
+
+    if to_stdout {
+        let format = options.output_format().expect("stdout always has a format");
+        return canvas.write_to(&mut io::stdout().lock(), format, &options.quantizer);
+    }
+
+    canvas
+        .save_as(
+            &options.output,
+            options.output_format(),
+            &options.quantizer,
+            options.overwrite,
+        )
+        .map_err(|e| e.with_path(&options.output))?;
+
+    if !options.quiet {
+        if let Some(stats) = shader.stats() {
+            println!("{}", stats);
+        }
+        println!("Done.");
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+//! What surfaces are made of: how they scatter and emit light.
+
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::vec3::Vec3;
+use std::f64::consts::TAU;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A ray leaving a surface, and how much of the light arriving along it reaches
+/// the viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scatter {
+    pub attenuation: Color,
+    pub ray: Ray,
+}
+
+/// How light interacts with a surface.
+///
+/// Materials are sampled: each call picks one direction light can arrive from,
+/// with the probability of each direction folded into the attenuation.
+pub trait Material: Send + Sync + Debug {
+    /// Where light reaching `hit` along `ray` comes from, or `None` if the surface
+    /// absorbs it.
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter>;
+
+    /// Light given off by the surface itself.
+    fn emitted(&self, _hit: &HitRecord) -> Color {
+        Color::BLACK
+    }
+}
+
+/// A uniformly distributed direction.
+fn random_unit_vector(rng: &mut Rng) -> Vec3 {
+    let z = 1.0 - 2.0 * rng.next_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let (sin, cos) = (TAU * rng.next_f64()).sin_cos();
+    Vec3::new(r * cos, r * sin, z)
+}
+
+/// A uniformly distributed point inside the unit sphere.
+fn random_in_unit_sphere(rng: &mut Rng) -> Vec3 {
+    random_unit_vector(rng) * rng.next_f64().cbrt()
+}
+
+/// A diffuse surface that scatters light evenly in all directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lambertian {
+    pub albedo: Color,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Lambertian {
+        Lambertian { albedo }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter> {
+        // Offsetting the normal by a unit vector picks directions by the cosine law.
+        let direction = hit.normal + random_unit_vector(rng);
+        let direction = if direction.near_zero() { hit.normal } else { direction };
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(hit.point, direction),
+        })
+    }
+}
+
+/// A mirror, blurred by reflecting in a random direction within `fuzz` of the
+/// perfect one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metal {
+    pub albedo: Color,
+    /// From 0 for a perfect mirror to 1 for a very rough surface.
+    pub fuzz: f64,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Metal {
+        Metal {
+            albedo,
+            fuzz: fuzz.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Material for Metal {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter> {
+        let reflected = ray.direction.normalize().reflect(hit.normal);
+        let direction = reflected + random_in_unit_sphere(rng) * self.fuzz;
+        // Fuzz can push the ray below the surface, where it is absorbed.
+        if direction.dot(hit.normal) <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(hit.point, direction),
+        })
+    }
+}
+
+/// A clear material like glass or water, which both reflects and refracts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dielectric {
+    /// The refractive index relative to the air around it.
+    pub index: f64,
+}
+
+impl Dielectric {
+    pub fn new(index: f64) -> Dielectric {
+        Dielectric { index }
+    }
+
+    /// Schlick's approximation of the share of light reflected at `cos_theta`.
+    pub fn reflectance(cos_theta: f64, eta_ratio: f64) -> f64 {
+        let r0 = ((1.0 - eta_ratio) / (1.0 + eta_ratio)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos_theta).powi(5)
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter> {
+        let eta_ratio = if hit.front_face { 1.0 / self.index } else { self.index };
+        let unit = ray.direction.normalize();
+        let cos_theta = (-unit).dot(hit.normal).min(1.0);
+        let direction = match unit.refract(hit.normal, eta_ratio) {
+            Some(refracted) if Dielectric::reflectance(cos_theta, eta_ratio) <= rng.next_f64() => refracted,
+            _ => unit.reflect(hit.normal),
+        };
+        Some(Scatter {
+            attenuation: Color::WHITE,
+            ray: Ray::new(hit.point, direction),
+        })
+    }
+}
+
+/// A surface that gives off light and reflects none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord, _rng: &mut Rng) -> Option<Scatter> {
+        None
+    }
+
+    /// Lights shine from their front face only, so that the back of a ceiling
+    /// panel stays dark.
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        if hit.front_face {
+            self.emit
+        } else {
+            Color::BLACK
+        }
+    }
+}
+
+/// The phase function of a participating medium like smoke or fog: light is
+/// scattered evenly in all directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Isotropic {
+    pub albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray: &Ray, hit: &HitRecord, rng: &mut Rng) -> Option<Scatter> {
+        Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(hit.point, random_unit_vector(rng)),
+        })
+    }
+}
+
+/// A shape made of a material. Hits that already carry a material, from objects
+/// nested inside, keep it.
+#[derive(Debug, Clone)]
+pub struct Surface<H> {
+    pub shape: H,
+    pub material: Arc<dyn Material>,
+}
+
+impl<H: Hittable> Surface<H> {
+    pub fn new<M: Material + 'static>(shape: H, material: M) -> Surface<H> {
+        Surface::shared(shape, Arc::new(material))
+    }
+
+    /// A surface with a material that other objects also use.
+    pub fn shared(shape: H, material: Arc<dyn Material>) -> Surface<H> {
+        Surface { shape, material }
+    }
+}
+
+impl<H: Hittable> Hittable for Surface<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit = self.shape.hit(ray, t_min, t_max)?;
+        hit.material = hit.material.or(Some(&*self.material));
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.shape.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    #[test]
+    fn schlick_reflectance_grows_toward_grazing_angles() {
+        // Glass reflects about 4% head on and everything at grazing angles.
+        let head_on = Dielectric::reflectance(1.0, 1.0 / 1.5);
+        assert!((head_on - 0.04).abs() < 1e-12);
+        assert!(Dielectric::reflectance(0.5, 1.0 / 1.5) > head_on);
+        assert!((Dielectric::reflectance(0.0, 1.0 / 1.5) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scattered_rays_leave_on_the_right_side() {
+        let ray = Ray::new(Point3::new(1.0, 1.0, 0.0), Vec3::new(-1.0, -1.0, 0.0));
+        let hit = HitRecord::new(&ray, 1.0, Vec3::new(0.0, 1.0, 0.0), (0.0, 0.0));
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let diffuse = Lambertian::new(Color::WHITE).scatter(&ray, &hit, &mut rng).unwrap();
+            assert!(diffuse.ray.direction.dot(hit.normal) >= 0.0);
+            assert_eq!(diffuse.ray.origin, Point3::ZERO);
+
+            let mirror = Metal::new(Color::WHITE, 0.0).scatter(&ray, &hit, &mut rng).unwrap();
+            assert!((mirror.ray.direction - Vec3::new(-1.0, 1.0, 0.0).normalize()).near_zero());
+
+            // Entering glass at 45 degrees mostly refracts, downwards.
+            let glass = Dielectric::new(1.5).scatter(&ray, &hit, &mut rng).unwrap();
+            assert_eq!(glass.attenuation, Color::WHITE);
+            assert!((glass.ray.direction.length() - 1.0).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,562 @@
+//! Triangle meshes, loaded from Wavefront `.obj` files and their `.mtl` material
+//! libraries.
+//!
+//! Only geometry and the basic material properties are read. Free-form curves,
+//! lines, points, smoothing groups and texture maps are skipped.
+
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::ray::Ray;
+use crate::shapes::Triangle;
+use crate::vec3::{Point3, Vec3};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// One corner of a face: indices into the vertex data of a [`Mesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vertex {
+    pub position: usize,
+    pub uv: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+/// A triangle of a mesh. Polygons are split into triangles when they are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+    pub vertices: [Vertex; 3],
+    /// Index into [`Mesh::groups`].
+    pub group: usize,
+    /// Index into [`Mesh::materials`], or `None` before the first `usemtl`.
+    pub material: Option<usize>,
+}
+
+/// A material named by a mesh, and what it was found to be in the libraries.
+#[derive(Debug, Clone)]
+pub struct MeshMaterial {
+    pub name: String,
+    /// `None` until the mesh's material libraries are read.
+    pub material: Option<Arc<dyn Material>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub normals: Vec<Vec3>,
+    pub faces: Vec<Face>,
+    /// The names of the groups and objects faces belong to, starting with
+    /// `default` for faces before the first `g` or `o`.
+    pub groups: Vec<String>,
+    pub materials: Vec<MeshMaterial>,
+}
+
+type ParseResult<T> = std::result::Result<T, (usize, String)>;
+
+/// A parsed `.obj` file, before its material libraries are read.
+struct ObjFile {
+    mesh: Mesh,
+    /// The libraries named by `mtllib`, with the line naming each.
+    libraries: Vec<(usize, String)>,
+    /// The line on which each of the mesh's materials is first used.
+    usages: Vec<usize>,
+}
+
+impl Mesh {
+    /// Loads an `.obj` file along with the material libraries it names, which are
+    /// looked for next to it.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+        let path = path.as_ref();
+        let located = |(line, message)| Error::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        let text = fs::read_to_string(path).map_err(|e| Error::from(e).with_path(path))?;
+        let ObjFile {
+            mut mesh,
+            libraries,
+            usages,
+        } = parse_obj(&text).map_err(located)?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut library = Vec::new();
+        for (line, name) in libraries {
+            let file = directory.join(&name);
+            let text = fs::read_to_string(&file)
+                .map_err(|e| located((line, format!("cannot read material library '{}': {}", name, e))))?;
+            library.extend(parse_mtl(&text).map_err(|(line, message)| Error::Parse {
+                path: file.clone(),
+                line,
+                message,
+            })?);
+        }
+        for (slot, line) in mesh.materials.iter_mut().zip(usages) {
+            // Later definitions replace earlier ones, as in most other readers.
+            match library.iter().rev().find(|(name, _)| *name == slot.name) {
+                Some((_, material)) => slot.material = Some(material.clone()),
+                None => return Err(located((line, format!("unknown material '{}'", slot.name)))),
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Reads `.obj` text, without loading any material libraries.
+    pub fn parse(text: &str) -> ParseResult<Mesh> {
+        parse_obj(text).map(|file| file.mesh)
+    }
+
+    /// The box around every vertex, or `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::around(&self.positions)
+    }
+
+    /// Every face as a separate object, to be placed in a [`Bvh`](crate::bvh::Bvh).
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+        let mesh = Arc::new(self);
+        (0..mesh.faces.len())
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect()
+    }
+}
+
+fn parse_obj(text: &str) -> ParseResult<ObjFile> {
+    let mut mesh = Mesh {
+        groups: vec!["default".to_string()],
+        ..Mesh::default()
+    };
+    let (mut libraries, mut usages) = (Vec::new(), Vec::new());
+    let (mut group, mut material) = (0, None);
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap();
+        let rest: Vec<&str> = words.collect();
+        let name = || {
+            if rest.is_empty() {
+                return Err((number, format!("expected a name after {}", keyword)));
+            }
+            Ok(rest.join(" "))
+        };
+
+        match keyword {
+            "v" => {
+                let v = numbers(&rest, 3, 4, keyword, number)?;
+                mesh.positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = numbers(&rest, 1, 3, keyword, number)?;
+                mesh.uvs.push((v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let v = numbers(&rest, 3, 3, keyword, number)?;
+                mesh.normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "f" => {
+                if rest.len() < 3 {
+                    return Err((number, format!("a face needs at least 3 vertices, found {}", rest.len())));
+                }
+                let corners = rest
+                    .iter()
+                    .map(|word| vertex(word, &mesh).map_err(|message| (number, message)))
+                    .collect::<ParseResult<Vec<Vertex>>>()?;
+                let points: Vec<Point3> = corners.iter().map(|c| mesh.positions[c.position]).collect();
+                for [a, b, c] in triangulate(&points) {
+                    mesh.faces.push(Face {
+                        vertices: [corners[a], corners[b], corners[c]],
+                        group,
+                        material,
+                    });
+                }
+            }
+            "g" | "o" => {
+                let name = name()?;
+                group = match mesh.groups.iter().position(|g| *g == name) {
+                    Some(index) => index,
+                    None => {
+                        mesh.groups.push(name);
+                        mesh.groups.len() - 1
+                    }
+                };
+            }
+            "usemtl" => {
+                let name = name()?;
+                material = Some(match mesh.materials.iter().position(|m| m.name == name) {
+                    Some(index) => index,
+                    None => {
+                        mesh.materials.push(MeshMaterial { name, material: None });
+                        usages.push(number);
+                        mesh.materials.len() - 1
+                    }
+                });
+            }
+            "mtllib" => libraries.extend(rest.iter().map(|name| (number, name.to_string()))),
+            "s" | "l" | "p" | "vp" | "cstype" | "deg" | "curv" | "curv2" | "surf" | "parm" | "end" | "mg" => {}
+            _ => return Err((number, format!("unknown statement '{}'", keyword))),
+        }
+    }
+
+    if mesh.faces.is_empty() {
+        return Err((0, "the file has no faces".to_string()));
+    }
+    Ok(ObjFile {
+        mesh,
+        libraries,
+        usages,
+    })
+}
+
+/// Parses between `min` and `max` numbers following `keyword`.
+fn numbers(words: &[&str], min: usize, max: usize, keyword: &str, line: usize) -> ParseResult<Vec<f64>> {
+    let values: Option<Vec<f64>> = words
+        .iter()
+        .map(|w| w.parse().ok().filter(|v: &f64| v.is_finite()))
+        .collect();
+    match values {
+        Some(values) if (min..=max).contains(&values.len()) => Ok(values),
+        _ if max == 1 => Err((line, format!("expected a number after {}", keyword))),
+        _ if min == max => Err((line, format!("expected {} numbers after {}", min, keyword))),
+        _ => Err((line, format!("expected {} to {} numbers after {}", min, max, keyword))),
+    }
+}
+
+/// Parses a face corner, `v`, `v/vt`, `v//vn` or `v/vt/vn`, with 1-based indices
+/// or negative ones counting back from the latest vertex.
+fn vertex(word: &str, mesh: &Mesh) -> std::result::Result<Vertex, String> {
+    let mut parts = word.split('/');
+    let mut index = |what: &str, count: usize, required: bool| -> std::result::Result<Option<usize>, String> {
+        let part = match parts.next() {
+            Some(part) if !part.is_empty() => part,
+            _ if required => return Err(format!("missing {} index in '{}'", what, word)),
+            _ => return Ok(None),
+        };
+        let resolved = match part.parse::<i64>() {
+            Ok(i) if i > 0 && i as usize <= count => Some(i as usize - 1),
+            Ok(i) if i < 0 && i.unsigned_abs() as usize <= count => Some(count - i.unsigned_abs() as usize),
+            Ok(_) => None,
+            Err(_) => return Err(format!("expected an index, found '{}'", part)),
+        };
+        match resolved {
+            Some(i) => Ok(Some(i)),
+            None => Err(format!("{} index {} is out of range, with {} defined so far", what, part, count)),
+        }
+    };
+    let position = index("vertex", mesh.positions.len(), true)?.unwrap();
+    let uv = index("texture coordinate", mesh.uvs.len(), false)?;
+    let normal = index("normal", mesh.normals.len(), false)?;
+    if parts.next().is_some() {
+        return Err(format!("expected at most 3 indices in '{}'", word));
+    }
+    Ok(Vertex { position, uv, normal })
+}
+
+/// Splits a planar polygon into triangles by ear clipping, so that concave
+/// polygons are handled too. Returns indices into `points`.
+fn triangulate(points: &[Point3]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    // Newell's method gives the normal of the polygon, even if it is not quite
+    // planar. Projecting along its largest component keeps the polygon's shape.
+    let normal = (0..n).fold(Vec3::ZERO, |sum, i| {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        sum + Vec3::new((a.y - b.y) * (a.z + b.z), (a.z - b.z) * (a.x + b.x), (a.x - b.x) * (a.y + b.y))
+    });
+    let (x, y) = match (normal.x.abs(), normal.y.abs(), normal.z.abs()) {
+        (nx, ny, nz) if nx >= ny && nx >= nz => (1, 2),
+        (_, ny, nz) if ny >= nz => (2, 0),
+        _ => (0, 1),
+    };
+    let flat: Vec<(f64, f64)> = points.iter().map(|p| (p[x], p[y])).collect();
+    let area = (0..n).fold(0.0, |sum, i| {
+        let (a, b) = (flat[i], flat[(i + 1) % n]);
+        sum + a.0 * b.1 - b.0 * a.1
+    });
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    // Positive for corners that turn the same way as the polygon.
+    let turn = |a: usize, b: usize, c: usize| cross(flat[a], flat[b], flat[c]) * area.signum();
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        // Starting from the second corner splits convex polygons into the usual fan.
+        let ear = (1..=m).map(|i| i % m).find(|&i| {
+            let (a, b, c) = (remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]);
+            turn(a, b, c) > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&p| p != a && p != b && p != c)
+                    .all(|&p| turn(a, b, p) < 0.0 || turn(b, c, p) < 0.0 || turn(c, a, p) < 0.0)
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+                remaining.remove(i);
+            }
+            // Degenerate or self-intersecting: fall back to a fan.
+            None => break,
+        }
+    }
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+/// The properties of an `.mtl` material that are used, with the format's defaults.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MtlProperties {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    shininess: f64,
+    index: f64,
+    opacity: f64,
+    illum: u32,
+}
+
+impl Default for MtlProperties {
+    fn default() -> Self {
+        MtlProperties {
+            diffuse: Color::gray(0.8),
+            specular: Color::BLACK,
+            emission: Color::BLACK,
+            shininess: 0.0,
+            index: 1.5,
+            opacity: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlProperties {
+    /// The closest of the renderer's materials. Emissive materials become lights,
+    /// transparent ones glass, reflective ones (`illum` 3, 5 or 8) metal with a
+    /// roughness from the shininess, and the rest diffuse.
+    fn material(&self) -> Arc<dyn Material> {
+        if self.emission.max_channel() > 0.0 {
+            Arc::new(DiffuseLight::new(self.emission))
+        } else if self.opacity < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            Arc::new(Dielectric::new(self.index))
+        } else if matches!(self.illum, 3 | 5 | 8) {
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            Arc::new(Metal::new(self.specular, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+/// Parses an `.mtl` file into its named materials, in order.
+fn parse_mtl(text: &str) -> ParseResult<Vec<(String, Arc<dyn Material>)>> {
+    let mut materials: Vec<(String, MtlProperties)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap();
+        let rest: Vec<&str> = words.collect();
+        if keyword == "newmtl" {
+            if rest.is_empty() {
+                return Err((number, "expected a name after newmtl".to_string()));
+            }
+            materials.push((rest.join(" "), MtlProperties::default()));
+            continue;
+        }
+
+        let current = match materials.last_mut() {
+            Some((_, properties)) => properties,
+            None => return Err((number, format!("{} before the first newmtl", keyword))),
+        };
+        let color = || -> ParseResult<Color> {
+            // A single value means gray.
+            let v = numbers(&rest, 1, 3, keyword, number)?;
+            match v.len() {
+                1 => Ok(Color::gray(v[0])),
+                3 => Ok(Color::new(v[0], v[1], v[2])),
+                _ => Err((number, format!("expected 1 or 3 numbers after {}", keyword))),
+            }
+        };
+        let number_after = || numbers(&rest, 1, 1, keyword, number).map(|v| v[0]);
+        match keyword {
+            "Kd" => current.diffuse = color()?,
+            "Ks" => current.specular = color()?,
+            "Ke" => current.emission = color()?,
+            "Ns" => current.shininess = number_after()?.max(0.0),
+            "Ni" => current.index = number_after()?,
+            "d" => current.opacity = number_after()?,
+            "Tr" => current.opacity = 1.0 - number_after()?,
+            "illum" => {
+                current.illum = match rest.as_slice() {
+                    [n] => n
+                        .parse()
+                        .map_err(|_| (number, format!("expected an illumination model, found '{}'", n)))?,
+                    _ => return Err((number, "expected one number after illum".to_string())),
+                }
+            }
+            // Ambient color, texture maps and the rest have no equivalent.
+            _ => {}
+        }
+    }
+    Ok(materials
+        .into_iter()
+        .map(|(name, properties)| (name, properties.material()))
+        .collect())
+}
+
+/// One face of a shared mesh.
+#[derive(Debug, Clone)]
+pub struct MeshTriangle {
+    pub mesh: Arc<Mesh>,
+    pub face: usize,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mesh = &*self.mesh;
+        let face = &mesh.faces[self.face];
+        let [a, b, c] = face.vertices.map(|v| mesh.positions[v.position]);
+        let triangle = Triangle::new(a, b, c);
+        let hit = triangle.hit(ray, t_min, t_max)?;
+
+        // Vertex attributes are blended by the barycentric weights of the hit.
+        let (u, v) = hit.uv;
+        let weights = [1.0 - u - v, u, v];
+        let uvs = face.vertices.map(|vertex| vertex.uv.map(|i| mesh.uvs[i]));
+        let uv = match uvs {
+            [Some(a), Some(b), Some(c)] => (
+                a.0 * weights[0] + b.0 * weights[1] + c.0 * weights[2],
+                a.1 * weights[0] + b.1 * weights[1] + c.1 * weights[2],
+            ),
+            _ => (u, v),
+        };
+        let geometric = (b - a).cross(c - a).normalize();
+        let mut record = HitRecord::new(ray, hit.t, geometric, uv);
+        if let [Some(na), Some(nb), Some(nc)] = face.vertices.map(|vertex| vertex.normal.map(|i| mesh.normals[i])) {
+            let shading = na * weights[0] + nb * weights[1] + nc * weights[2];
+            if !shading.near_zero() {
+                // Smooth, but on the side of the surface the ray is on.
+                let shading = shading.normalize();
+                record.normal = if shading.dot(record.normal) < 0.0 { -shading } else { shading };
+            }
+        }
+        record.material = face.material.and_then(|m| mesh.materials[m].material.as_deref());
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let face = &self.mesh.faces[self.face];
+        Aabb::around(&face.vertices.map(|v| self.mesh.positions[v.position]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_vertices_faces_groups_and_materials() {
+        let text = "\
+# A unit square and a triangle above it.
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g floor
+usemtl stone
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g roof
+usemtl glass
+v 0 0 1
+f -1 -3 -2
+s off
+";
+        let mesh = Mesh::parse(text).unwrap();
+        assert_eq!(mesh.positions.len(), 5);
+        assert_eq!(mesh.faces.len(), 3);
+        assert_eq!(mesh.groups, ["default", "floor", "roof"]);
+        let names: Vec<&str> = mesh.materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["stone", "glass"]);
+
+        let quad = &mesh.faces[..2];
+        assert!(quad.iter().all(|f| f.group == 1 && f.material == Some(0)));
+        assert_eq!(quad[0].vertices[1], Vertex { position: 1, uv: Some(1), normal: Some(0) });
+        // Negative indices count back from the latest vertex.
+        let roof = mesh.faces[2];
+        assert_eq!(roof.vertices.map(|v| v.position), [4, 2, 3]);
+        assert_eq!((roof.group, roof.material), (2, Some(1)));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        let error = |text: &str| Mesh::parse(text).unwrap_err();
+        assert_eq!(error("v 0 0 0\nv 1 0\n").0, 2);
+        let (line, message) = error("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 4\n");
+        assert_eq!((line, message.as_str()), (5, "vertex index 4 is out of range, with 3 defined so far"));
+        assert_eq!(error("v 0 0 0\nv 1 0 0\nf 1 2\n").0, 3);
+        assert_eq!(error("v 0 0 0\nf 1/x 1 1\n").0, 2);
+        assert_eq!(error("bogus 1 2 3\n").0, 1);
+        assert_eq!(error("v 0 0 0\n").0, 0);
+        assert_eq!(parse_mtl("Kd 1 0 0\n").unwrap_err().0, 1);
+        assert_eq!(parse_mtl("newmtl red\nKd 1 0\n").unwrap_err().0, 2);
+    }
+
+    #[test]
+    fn concave_polygons_are_split_inside_their_outline() {
+        // An L shape, whose fan from the first corner would cover the notch.
+        let points = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]
+            .iter()
+            .map(|&(x, y)| Point3::new(x, 0.0, y))
+            .collect::<Vec<_>>();
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 4);
+        let area: f64 = triangles
+            .iter()
+            .map(|&[a, b, c]| (points[b] - points[a]).cross(points[c] - points[a]).length() / 2.0)
+            .sum();
+        assert!((area - 3.0).abs() < 1e-12, "area {}", area);
+    }
+
+    #[test]
+    fn unreadable_files_are_named_in_the_error() {
+        let path = std::env::temp_dir().join("output-image-missing.obj");
+        let error = Mesh::load(&path).unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+        assert!(error.to_string().starts_with(&format!("{}: ", path.display())), "{}", error);
+    }
+
+    #[test]
+    fn smooth_normals_are_interpolated() {
+        let text = "v -1 0 -1\nv 1 0 -1\nv 0 0 1\nvn -1 1 0\nvn 1 1 0\nvn 0 1 0\nf 1//1 3//3 2//2\n";
+        let triangle = &Mesh::parse(text).unwrap().into_objects()[0];
+        let ray = Ray::new(Point3::new(0.0, 1.0, -1.0 / 3.0), Vec3::new(0.0, -1.0, 0.0));
+        let hit = triangle.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-12);
+        assert!((hit.normal - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+        assert!(hit.material.is_none());
+
+        let ray = Ray::new(Point3::new(-0.5, 1.0, -0.9), Vec3::new(0.0, -1.0, 0.0));
+        let hit = triangle.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!(hit.normal.x < 0.0 && hit.normal.y > 0.0);
+    }
+}
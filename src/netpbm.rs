@@ -0,0 +1,366 @@
+//! Encoder and decoder for the Netpbm family: PPM, PGM and PAM.
+//!
+//! These are the formats from the book, written without going through the `image`
+//! crate so they can be streamed to stdout or any other `Write`.
+
+use crate::canvas::Canvas;
+use image::{Rgb, RgbImage};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::str::FromStr;
+
+/// A Netpbm flavour and its encoding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// `P3`: color, ASCII decimal samples.
+    PpmAscii,
+    /// `P6`: color, raw binary samples.
+    Ppm,
+    /// `P2`: grayscale, ASCII decimal samples.
+    PgmAscii,
+    /// `P5`: grayscale, raw binary samples.
+    Pgm,
+    /// `P7`: portable arbitrary map with an `RGB` tuple type.
+    Pam,
+}
+
+impl Format {
+    pub fn magic(self) -> &'static str {
+        match self {
+            Format::PpmAscii => "P3",
+            Format::Ppm => "P6",
+            Format::PgmAscii => "P2",
+            Format::Pgm => "P5",
+            Format::Pam => "P7",
+        }
+    }
+
+    /// Picks the binary variant matching a file extension.
+    pub fn from_extension(ext: &str) -> Option<Format> {
+        match ext.to_ascii_lowercase().as_str() {
+            "ppm" | "pnm" => Some(Format::Ppm),
+            "pgm" => Some(Format::Pgm),
+            "pam" => Some(Format::Pam),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ppm" => Ok(Format::Ppm),
+            "ppm-ascii" => Ok(Format::PpmAscii),
+            "pgm" => Ok(Format::Pgm),
+            "pgm-ascii" => Ok(Format::PgmAscii),
+            "pam" => Ok(Format::Pam),
+            _ => Err(format!("unknown Netpbm format '{}'", s)),
+        }
+    }
+}
+
+/// Writes `image` to `w` in the given format with a maximum sample value of 255.
+pub fn write<W: Write>(w: &mut W, image: &RgbImage, format: Format) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+
+    match format {
+        Format::PpmAscii | Format::Ppm | Format::PgmAscii | Format::Pgm => {
+            writeln!(w, "{}\n{} {}\n255", format.magic(), width, height)?;
+        }
+        Format::Pam => {
+            writeln!(
+                w,
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR",
+                width, height
+            )?;
+        }
+    }
+
+    let pixels = image.pixels();
+    match format {
+        Format::PpmAscii => {
+            // One pixel per line keeps us well below the 70 character line limit.
+            for Rgb([r, g, b]) in pixels {
+                writeln!(w, "{} {} {}", r, g, b)?;
+            }
+        }
+        Format::PgmAscii => {
+            for pixel in pixels {
+                writeln!(w, "{}", luma(*pixel))?;
+            }
+        }
+        Format::Ppm | Format::Pam => w.write_all(image)?,
+        Format::Pgm => {
+            let gray: Vec<u8> = pixels.map(|p| luma(*p)).collect();
+            w.write_all(&gray)?;
+        }
+    }
+
+    w.flush()
+}
+
+/// Reads any PPM, PGM or PAM image. Grayscale images are expanded to RGB, samples
+/// with a larger range than 255 are scaled down and any alpha channel is dropped.
+pub fn read<R: BufRead>(r: &mut R) -> Result<(Format, RgbImage), DecodeError> {
+    let mut header = HeaderReader { r };
+
+    let magic = header.token()?;
+    let (format, width, height, depth, maxval) = match magic.as_str() {
+        "P2" | "P3" | "P5" | "P6" => {
+            let format = match magic.as_str() {
+                "P2" => Format::PgmAscii,
+                "P3" => Format::PpmAscii,
+                "P5" => Format::Pgm,
+                _ => Format::Ppm,
+            };
+            let width = header.number("width")?;
+            let height = header.number("height")?;
+            let maxval = header.number("maxval")?;
+            let depth = if magic == "P2" || magic == "P5" { 1 } else { 3 };
+            (format, width, height, depth, maxval)
+        }
+        "P7" => {
+            let (mut width, mut height, mut depth, mut maxval) = (None, None, None, None);
+            loop {
+                let key = header.token()?;
+                match key.as_str() {
+                    "WIDTH" => width = Some(header.number("WIDTH")?),
+                    "HEIGHT" => height = Some(header.number("HEIGHT")?),
+                    "DEPTH" => depth = Some(header.number("DEPTH")?),
+                    "MAXVAL" => maxval = Some(header.number("MAXVAL")?),
+                    "TUPLTYPE" => header.rest_of_line()?,
+                    "ENDHDR" => break,
+                    _ => return Err(DecodeError::Invalid(format!("unknown PAM header field '{}'", key))),
+                }
+            }
+            let missing = |name: &str| DecodeError::Invalid(format!("PAM header is missing {}", name));
+            (
+                Format::Pam,
+                width.ok_or_else(|| missing("WIDTH"))?,
+                height.ok_or_else(|| missing("HEIGHT"))?,
+                depth.ok_or_else(|| missing("DEPTH"))?,
+                maxval.ok_or_else(|| missing("MAXVAL"))?,
+            )
+        }
+        _ => return Err(DecodeError::Invalid(format!("unknown magic number '{}'", magic))),
+    };
+
+    if maxval == 0 || maxval > 65535 {
+        return Err(DecodeError::Invalid(format!("maxval {} is out of range", maxval)));
+    }
+    if depth == 0 || depth > 4 {
+        return Err(DecodeError::Unsupported(format!("tuple depth {}", depth)));
+    }
+
+    // The header is untrusted, so its size is checked before anything is allocated.
+    let pixels = (width as u64)
+        .checked_mul(height as u64)
+        .filter(|&pixels| pixels <= Canvas::MAX_PIXELS)
+        .ok_or_else(|| DecodeError::Unsupported(format!("image size {}x{}", width, height)))?;
+    let count = pixels as usize * depth as usize;
+    let samples = match format {
+        Format::PpmAscii | Format::PgmAscii => {
+            let mut samples = Vec::with_capacity(count.min(1 << 16));
+            for _ in 0..count {
+                samples.push(header.number("sample")?);
+            }
+            samples
+        }
+        _ => {
+            // Exactly one whitespace character separates the header from the raster.
+            // Reading through `take` only allocates as much as the file holds.
+            let wide = maxval > 255;
+            let len = if wide { count * 2 } else { count };
+            let mut raw = Vec::new();
+            (&mut *header.r).take(len as u64).read_to_end(&mut raw)?;
+            if raw.len() < len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            if wide {
+                raw.chunks(2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32).collect()
+            } else {
+                raw.into_iter().map(u32::from).collect()
+            }
+        }
+    };
+
+    let scale = |v: u32| -> Result<u8, DecodeError> {
+        if v > maxval {
+            return Err(DecodeError::Invalid(format!("sample {} exceeds maxval {}", v, maxval)));
+        }
+        Ok(((v * 255 + maxval / 2) / maxval) as u8)
+    };
+
+    let mut image = RgbImage::new(width, height);
+    let depth = depth as usize;
+    for (i, tuple) in samples.chunks(depth).enumerate() {
+        let color = if depth < 3 {
+            let l = scale(tuple[0])?;
+            Rgb([l, l, l])
+        } else {
+            Rgb([scale(tuple[0])?, scale(tuple[1])?, scale(tuple[2])?])
+        };
+        image.put_pixel(i as u32 % width, i as u32 / width, color);
+    }
+
+    Ok((format, image))
+}
+
+// Rec. 709 luma weights, in integer arithmetic.
+fn luma(Rgb([r, g, b]): Rgb<u8>) -> u8 {
+    ((2126 * r as u32 + 7152 * g as u32 + 722 * b as u32 + 5000) / 10000) as u8
+}
+
+struct HeaderReader<'a, R> {
+    r: &'a mut R,
+}
+
+impl<'a, R: BufRead> HeaderReader<'a, R> {
+    fn byte(&mut self) -> Result<Option<u8>, DecodeError> {
+        let mut b = [0];
+        match self.r.read(&mut b)? {
+            0 => Ok(None),
+            _ => Ok(Some(b[0])),
+        }
+    }
+
+    /// Reads the next whitespace separated token, skipping `#` comments. Consumes
+    /// the single whitespace character that terminates it.
+    fn token(&mut self) -> Result<String, DecodeError> {
+        let mut token = String::new();
+        loop {
+            match self.byte()? {
+                None if token.is_empty() => return Err(DecodeError::Invalid("unexpected end of file".into())),
+                None => return Ok(token),
+                Some(b'#') if token.is_empty() => self.rest_of_line()?,
+                Some(b) if b.is_ascii_whitespace() => {
+                    if !token.is_empty() {
+                        return Ok(token);
+                    }
+                }
+                Some(b) => token.push(b as char),
+            }
+        }
+    }
+
+    fn number(&mut self, what: &str) -> Result<u32, DecodeError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| DecodeError::Invalid(format!("invalid {} '{}'", what, token)))
+    }
+
+    fn rest_of_line(&mut self) -> Result<(), DecodeError> {
+        while let Some(b) = self.byte()? {
+            if b == b'\n' {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a Netpbm image could not be read.
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    /// The data is not a well-formed Netpbm image.
+    Invalid(String),
+    /// The image is valid but uses a feature this decoder does not handle.
+    Unsupported(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "{}", e),
+            DecodeError::Invalid(msg) => write!(f, "invalid Netpbm image: {}", msg),
+            DecodeError::Unsupported(msg) => write!(f, "unsupported Netpbm image: {}", msg),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Generator, Quantizer};
+
+    fn gradient() -> RgbImage {
+        crate::render(Generator::Gradient, 7, 5).to_rgb8(&Quantizer::default())
+    }
+
+    fn round_trip(format: Format) -> RgbImage {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &gradient(), format).unwrap();
+        let (read_format, decoded) = read(&mut &bytes[..]).unwrap();
+        assert_eq!(read_format, format);
+        assert_eq!(decoded.dimensions(), (7, 5));
+        decoded
+    }
+
+    #[test]
+    fn color_formats_round_trip_exactly() {
+        let image = gradient();
+        for &format in &[Format::PpmAscii, Format::Ppm, Format::Pam] {
+            assert_eq!(round_trip(format), image, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn gray_formats_round_trip_as_luma() {
+        let image = gradient();
+        for &format in &[Format::PgmAscii, Format::Pgm] {
+            let decoded = round_trip(format);
+            for (x, y, pixel) in decoded.enumerate_pixels() {
+                let l = luma(*image.get_pixel(x, y));
+                assert_eq!(*pixel, Rgb([l, l, l]));
+            }
+        }
+    }
+
+    #[test]
+    fn reads_comments_and_sixteen_bit_samples() {
+        let mut data = b"P6\n# a comment\n1 1\n65535\n".to_vec();
+        data.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let (_, image) = read(&mut &data[..]).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgb([255, 128, 0]));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let data = b"P6\n2 2\n255\n\x00\x00\x00";
+        assert!(matches!(read(&mut &data[..]), Err(DecodeError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_oversized_headers_before_allocating() {
+        let headers: [&[u8]; 3] = [
+            b"P6\n4294967295 4294967295\n255\n",
+            b"P3\n65536 65536\n255\n",
+            b"P7\nWIDTH 9000\nHEIGHT 9000\nDEPTH 4\nMAXVAL 255\nENDHDR\n",
+        ];
+        for data in &headers {
+            assert!(matches!(read(&mut &data[..]), Err(DecodeError::Unsupported(_))));
+        }
+        let error = crate::Error::from(read(&mut &b"P5\n70000 70000\n255\n"[..]).unwrap_err());
+        assert_eq!(error.exit_code(), 4);
+    }
+}
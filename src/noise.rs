@@ -0,0 +1,763 @@
+//! Seeded procedural noise in two, three and four dimensions.
+//!
+//! The base noises are [`Perlin`], [`OpenSimplex`], [`Value`] and [`Worley`]. They can
+//! be layered with [`Fractal`] (fBm, turbulence, ridged multifractal) and bent with
+//! [`Warp`]. Everything is a pure function of the seed and the position, so renders
+//! are reproducible and independent of the number of threads.
+
+use crate::colormap::ScalarField;
+use crate::rng::mix;
+use crate::shader::ShadeContext;
+use std::array;
+use std::str::FromStr;
+
+/// A noise function. Results lie roughly within [`range`](Noise::range).
+pub trait Noise: Sync {
+    fn noise2(&self, p: [f64; 2]) -> f64;
+    fn noise3(&self, p: [f64; 3]) -> f64;
+    fn noise4(&self, p: [f64; 4]) -> f64;
+
+    /// The nominal range of the output, `-1.0..1.0` unless stated otherwise.
+    fn range(&self) -> (f64, f64) {
+        (-1.0, 1.0)
+    }
+}
+
+impl<N: Noise + ?Sized> Noise for Box<N> {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        (**self).noise2(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        (**self).noise3(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        (**self).noise4(p)
+    }
+
+    fn range(&self) -> (f64, f64) {
+        (**self).range()
+    }
+}
+
+/// Evaluates `noise` at a point of any supported dimension.
+pub fn sample<N: Noise + ?Sized, const D: usize>(noise: &N, p: [f64; D]) -> f64 {
+    match D {
+        2 => noise.noise2([p[0], p[1]]),
+        3 => noise.noise3([p[0], p[1], p[2]]),
+        4 => noise.noise4([p[0], p[1], p[2], p[3]]),
+        _ => panic!("noise is only defined in 2, 3 and 4 dimensions"),
+    }
+}
+
+/// A 64-bit hash of a lattice point.
+fn hash<const D: usize>(seed: u64, lattice: [i64; D]) -> u64 {
+    lattice
+        .iter()
+        .fold(mix(seed), |h, &v| mix(h.wrapping_add(v as u64).wrapping_add(0x9e37_79b9_7f4a_7c15)))
+}
+
+/// A uniformly distributed value in `0.0..1.0` taken from the high bits of `h`.
+fn unit(h: u64) -> f64 {
+    (h >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// A pseudo-random gradient for a lattice point. In 3D and 4D these are Perlin's
+/// vectors to the edge midpoints of the cube, with one zero and the rest ±1; in 2D
+/// the eight compass directions.
+fn gradient<const D: usize>(h: u64) -> [f64; D] {
+    if D == 2 {
+        const E: f64 = std::f64::consts::FRAC_1_SQRT_2;
+        const DIRECTIONS: [[f64; 2]; 8] = [
+            [1.0, 0.0],
+            [-1.0, 0.0],
+            [0.0, 1.0],
+            [0.0, -1.0],
+            [E, E],
+            [-E, E],
+            [E, -E],
+            [-E, -E],
+        ];
+        let g = DIRECTIONS[(h >> 61) as usize];
+        return array::from_fn(|i| g[i]);
+    }
+    let zero = ((h >> 32) % D as u64) as usize;
+    array::from_fn(|i| match i {
+        _ if i == zero => 0.0,
+        _ if h >> i & 1 == 1 => -1.0,
+        _ => 1.0,
+    })
+}
+
+fn dot<const D: usize>(a: [f64; D], b: [f64; D]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Perlin's quintic fade curve, which has zero first and second derivatives at the
+/// lattice points.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Interpolates between the values at the `2^D` corners of a lattice cell. Bit `i`
+/// of a corner's index says whether it is on the far side along axis `i`.
+fn interpolate_corners<const D: usize>(mut corners: [f64; 16], weights: [f64; D]) -> f64 {
+    let mut count = 1 << D;
+    for &w in &weights {
+        count /= 2;
+        for c in 0..count {
+            corners[c] = corners[2 * c] + (corners[2 * c + 1] - corners[2 * c]) * w;
+        }
+    }
+    corners[0]
+}
+
+/// Splits a point into the lattice cell it is in and its offset within that cell.
+fn cell<const D: usize>(p: [f64; D]) -> ([i64; D], [f64; D]) {
+    let floor = p.map(f64::floor);
+    (floor.map(|v| v as i64), array::from_fn(|i| p[i] - floor[i]))
+}
+
+/// Improved Perlin gradient noise.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Perlin {
+    pub seed: u64,
+}
+
+impl Perlin {
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let (base, offset) = cell(p);
+        let mut corners = [0.0; 16];
+        for (c, value) in corners.iter_mut().enumerate().take(1 << D) {
+            let side: [i64; D] = array::from_fn(|i| (c >> i & 1) as i64);
+            let corner: [i64; D] = array::from_fn(|i| base[i] + side[i]);
+            let g = gradient::<D>(hash(self.seed, corner));
+            *value = dot(g, array::from_fn(|i| offset[i] - side[i] as f64));
+        }
+        // The largest possible value is at the center of a cell whose corner
+        // gradients all point at it; dividing by it keeps results within ±1.
+        let scale = match D {
+            2 => std::f64::consts::SQRT_2,
+            3 => 1.0,
+            _ => 2.0 / 3.0,
+        };
+        scale * interpolate_corners(corners, offset.map(fade))
+    }
+}
+
+/// OpenSimplex noise: Kurt Spencer's gradient noise on a lattice of simplices,
+/// created to avoid the patent on Perlin's simplex noise. Smoother than Perlin
+/// noise, and without its axis-aligned artifacts.
+///
+/// A point is stretched onto a hypercubic lattice, and every lattice point whose
+/// bump reaches it contributes `(2 - |d|²)^4` times the dot product of its gradient
+/// with the offset `d`, measured back in the original space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OpenSimplex {
+    pub seed: u64,
+}
+
+impl OpenSimplex {
+    /// The gradients have one large component and the rest small, with random
+    /// signs: Spencer's 8 vectors in 2D, 24 in 3D and 64 in 4D.
+    fn gradient<const D: usize>(h: u64) -> [f64; D] {
+        let (large, small) = match D {
+            2 => (5.0, 2.0),
+            3 => (11.0, 4.0),
+            _ => (3.0, 1.0),
+        };
+        let axis = ((h >> 32) % D as u64) as usize;
+        array::from_fn(|i| {
+            let v = if i == axis { large } else { small };
+            if h >> i & 1 == 1 {
+                -v
+            } else {
+                v
+            }
+        })
+    }
+
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let n = D as f64;
+        let stretch = (1.0 / (n + 1.0).sqrt() - 1.0) / n;
+        let squish = ((n + 1.0).sqrt() - 1.0) / n;
+
+        // Every lattice point whose bump reaches `p` lies within -1..=2 of the
+        // stretched cell along each axis.
+        let s = p.iter().sum::<f64>() * stretch;
+        let base: [i64; D] = array::from_fn(|i| (p[i] + s).floor() as i64);
+        let mut total = 0.0;
+        for index in 0..4usize.pow(D as u32) {
+            let vertex: [i64; D] = array::from_fn(|i| base[i] + (index >> (2 * i) & 3) as i64 - 1);
+            let t = vertex.iter().sum::<i64>() as f64 * squish;
+            let d: [f64; D] = array::from_fn(|i| p[i] - (vertex[i] as f64 + t));
+            let attenuation = 2.0 - dot(d, d);
+            if attenuation > 0.0 {
+                let g = OpenSimplex::gradient::<D>(hash(self.seed, vertex));
+                total += attenuation.powi(4) * dot(g, d);
+            }
+        }
+        // Spencer's normalization, which keeps results within ±1.
+        let norm = match D {
+            2 => 47.0,
+            3 => 103.0,
+            _ => 30.0,
+        };
+        total / norm
+    }
+}
+
+/// Value noise: random values at the lattice points, smoothly interpolated. Blockier
+/// than gradient noise, but cheap and with a predictable histogram.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Value {
+    pub seed: u64,
+}
+
+impl Value {
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let (base, offset) = cell(p);
+        let mut corners = [0.0; 16];
+        for (c, value) in corners.iter_mut().enumerate().take(1 << D) {
+            let corner: [i64; D] = array::from_fn(|i| base[i] + (c >> i & 1) as i64);
+            *value = unit(hash(self.seed, corner)) * 2.0 - 1.0;
+        }
+        interpolate_corners(corners, offset.map(fade))
+    }
+}
+
+/// How distances to feature points are measured.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Metric {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+/// Which distance Worley noise returns.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Feature {
+    /// The distance to the nearest point: round cells, dark at their centers.
+    #[default]
+    F1,
+    /// The distance to the second nearest point.
+    F2,
+    /// The difference of the two, which is zero along cell borders.
+    F2MinusF1,
+}
+
+/// Worley, or cellular, noise: distances to random feature points, one per lattice
+/// cell. Results lie roughly in `0.0..1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Worley {
+    pub seed: u64,
+    pub metric: Metric,
+    pub feature: Feature,
+}
+
+impl Metric {
+    fn distance<const D: usize>(self, d: [f64; D]) -> f64 {
+        match self {
+            Metric::Euclidean => dot(d, d).sqrt(),
+            Metric::Manhattan => d.iter().map(|v| v.abs()).sum(),
+            Metric::Chebyshev => d.iter().fold(0.0, |m, v| v.abs().max(m)),
+        }
+    }
+}
+
+impl Worley {
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let (base, offset) = cell(p);
+        let (mut f1, mut f2) = (f64::INFINITY, f64::INFINITY);
+
+        // The nearest two points can be more than one cell away, so search rings
+        // of cells at growing distances, skipping cells that cannot hold a point
+        // nearer than the second nearest found so far. Every cell in ring `r` is at
+        // least `r - 1` away along some axis.
+        for r in 0i64.. {
+            if r as f64 - 1.0 >= f2 {
+                break;
+            }
+            let side = 2 * r as usize + 1;
+            for index in 0..side.pow(D as u32) {
+                let step: [i64; D] = array::from_fn(|i| (index / side.pow(i as u32) % side) as i64 - r);
+                if step.iter().all(|s| s.abs() < r) {
+                    continue;
+                }
+                let gap: [f64; D] = array::from_fn(|i| match step[i] {
+                    s if s > 0 => s as f64 - offset[i],
+                    s if s < 0 => offset[i] - (s + 1) as f64,
+                    _ => 0.0,
+                });
+                if self.metric.distance(gap) >= f2 {
+                    continue;
+                }
+
+                let lattice: [i64; D] = array::from_fn(|i| base[i] + step[i]);
+                let mut h = hash(self.seed, lattice);
+                let d: [f64; D] = array::from_fn(|i| {
+                    h = mix(h);
+                    lattice[i] as f64 + unit(h) - p[i]
+                });
+                let distance = self.metric.distance(d);
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        match self.feature {
+            Feature::F1 => f1,
+            Feature::F2 => f2,
+            Feature::F2MinusF1 => f2 - f1,
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+}
+
+impl Noise for OpenSimplex {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+}
+
+impl Noise for Value {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+}
+
+impl Noise for Worley {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+
+    fn range(&self) -> (f64, f64) {
+        (0.0, 1.0)
+    }
+}
+
+/// How octaves of a [`Fractal`] are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FractalKind {
+    /// Fractional Brownian motion: a plain sum of octaves.
+    #[default]
+    Fbm,
+    /// The sum of absolute values, which gives billowy, cloud-like shapes.
+    Turbulence,
+    /// Musgrave's ridged multifractal: sharp crests that get rougher where they
+    /// are high, like mountain ranges.
+    Ridged,
+}
+
+/// Several octaves of a noise, each `lacunarity` times the frequency and `gain`
+/// times the amplitude of the previous one. fBm keeps the range of the underlying
+/// noise; turbulence and ridged results lie in `0.0..1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fractal<N> {
+    pub noise: N,
+    pub kind: FractalKind,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl<N: Noise> Fractal<N> {
+    /// Five octaves of fBm with the usual lacunarity of 2 and gain of 0.5.
+    pub fn new(noise: N, kind: FractalKind) -> Fractal<N> {
+        Fractal {
+            noise,
+            kind,
+            octaves: 5,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let (low, high) = self.noise.range();
+        let fold = |n: f64| ((n - low) / (high - low) * 2.0 - 1.0).abs();
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+        let (mut total, mut norm) = (0.0, 0.0);
+        let mut weight = 1.0;
+
+        for octave in 0..self.octaves {
+            // Shift the later octaves so that they do not all line up at the origin.
+            // The first is left in place, so one octave is the noise itself.
+            let h = mix(octave as u64);
+            let shift = |i: usize| if octave == 0 { 0.0 } else { unit(mix(h ^ i as u64)) * 1000.0 };
+            let q: [f64; D] = array::from_fn(|i| p[i] * frequency + shift(i));
+            let n = sample(&self.noise, q);
+            let signal = match self.kind {
+                FractalKind::Fbm => n,
+                // Folded at the middle of the range, so 0 where the noise crosses it.
+                FractalKind::Turbulence => fold(n),
+                FractalKind::Ridged => {
+                    let ridge = 1.0 - fold(n);
+                    let signal = ridge * ridge * weight;
+                    weight = (signal * 2.0).clamp(0.0, 1.0);
+                    signal
+                }
+            };
+            total += signal * amplitude;
+            norm += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        if norm > 0.0 {
+            total / norm
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<N: Noise> Noise for Fractal<N> {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+
+    fn range(&self) -> (f64, f64) {
+        match self.kind {
+            FractalKind::Fbm => self.noise.range(),
+            FractalKind::Turbulence | FractalKind::Ridged => (0.0, 1.0),
+        }
+    }
+}
+
+/// Domain warping: `noise` is sampled at a position displaced by `strength` times
+/// the value of `warp`, with a differently offset sample of `warp` for each axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Warp<N, W> {
+    pub noise: N,
+    pub warp: W,
+    pub strength: f64,
+}
+
+impl<N: Noise, W: Noise> Warp<N, W> {
+    fn sample<const D: usize>(&self, p: [f64; D]) -> f64 {
+        let displaced: [f64; D] = array::from_fn(|axis| {
+            let shifted: [f64; D] = array::from_fn(|i| p[i] + 5.2 * (axis + 1) as f64 + 1.7 * i as f64);
+            p[axis] + self.strength * sample(&self.warp, shifted)
+        });
+        sample(&self.noise, displaced)
+    }
+}
+
+impl<N: Noise, W: Noise> Noise for Warp<N, W> {
+    fn noise2(&self, p: [f64; 2]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise3(&self, p: [f64; 3]) -> f64 {
+        self.sample(p)
+    }
+
+    fn noise4(&self, p: [f64; 4]) -> f64 {
+        self.sample(p)
+    }
+
+    fn range(&self) -> (f64, f64) {
+        self.noise.range()
+    }
+}
+
+/// Samples a noise over the image, for coloring with a
+/// [`Colormapped`](crate::colormap::Colormapped) shader.
+///
+/// Pixel positions are divided by `scale`, so features are about `scale` pixels
+/// across. In 3D the third axis is the animation time, so the pattern evolves
+/// smoothly; in 4D time instead goes around a circle in the last two axes and the
+/// animation loops every `period` seconds.
+pub struct NoiseField {
+    pub noise: Box<dyn Noise>,
+    pub scale: f64,
+    pub dimensions: u32,
+    pub period: f64,
+}
+
+impl ScalarField for NoiseField {
+    fn value(&self, ctx: &ShadeContext) -> f64 {
+        let (x, y) = (ctx.x / self.scale, ctx.y / self.scale);
+        match self.dimensions {
+            2 => self.noise.noise2([x, y]),
+            3 => self.noise.noise3([x, y, ctx.time]),
+            _ => {
+                // A circle with a circumference of one unit per second of the loop.
+                let radius = self.period / std::f64::consts::TAU;
+                let (sin, cos) = (ctx.time / self.period * std::f64::consts::TAU).sin_cos();
+                self.noise.noise4([x, y, radius * cos, radius * sin])
+            }
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "euclidean" => Ok(Metric::Euclidean),
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            _ => Err(format!("unknown metric '{}' (expected euclidean, manhattan or chebyshev)", s)),
+        }
+    }
+}
+
+impl FromStr for Feature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f1" => Ok(Feature::F1),
+            "f2" => Ok(Feature::F2),
+            "f2-f1" => Ok(Feature::F2MinusF1),
+            _ => Err(format!("unknown feature '{}' (expected f1, f2 or f2-f1)", s)),
+        }
+    }
+}
+
+impl FromStr for FractalKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fbm" => Ok(FractalKind::Fbm),
+            "turbulence" => Ok(FractalKind::Turbulence),
+            "ridged" => Ok(FractalKind::Ridged),
+            _ => Err(format!("unknown fractal '{}' (expected none, fbm, turbulence or ridged)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn points<const D: usize>(count: usize) -> Vec<[f64; D]> {
+        let mut rng = Rng::new(11);
+        (0..count).map(|_| array::from_fn(|_| rng.next_f64() * 40.0 - 20.0)).collect()
+    }
+
+    fn noises(seed: u64) -> Vec<Box<dyn Noise>> {
+        vec![
+            Box::new(Perlin { seed }),
+            Box::new(OpenSimplex { seed }),
+            Box::new(Value { seed }),
+            Box::new(Worley { seed, ..Default::default() }),
+        ]
+    }
+
+    fn same_seed_same_noise<const D: usize>() {
+        let p = points::<D>(50);
+        for (a, (b, c)) in noises(7).iter().zip(noises(7).iter().zip(noises(8))) {
+            assert!(p.iter().all(|&p| sample(&**a, p) == sample(&**b, p)));
+            assert!(p.iter().any(|&p| sample(&**a, p) != sample(&*c, p)));
+        }
+    }
+
+    #[test]
+    fn noise_depends_only_on_the_seed_and_position() {
+        same_seed_same_noise::<2>();
+        same_seed_same_noise::<3>();
+        same_seed_same_noise::<4>();
+    }
+
+    fn extremes<const D: usize>(noise: &dyn Noise) -> (f64, f64) {
+        let values = points::<D>(20_000).into_iter().map(|p| sample(noise, p));
+        values.fold((0.0, 0.0), |(low, high), v| (v.min(low), v.max(high)))
+    }
+
+    #[test]
+    fn gradient_and_value_noise_fill_their_range_in_every_dimension() {
+        for noise in &noises(3)[..3] {
+            for (low, high) in [extremes::<2>(&**noise), extremes::<3>(&**noise), extremes::<4>(&**noise)] {
+                assert!(low >= -1.0 && high <= 1.0, "{:?} out of range", (low, high));
+                // The scale is not so cautious that most of the range goes unused.
+                assert!(low < -0.5 && high > 0.5, "{:?} too narrow", (low, high));
+            }
+        }
+    }
+
+    /// F1 and F2 found by checking every cell up to three away.
+    fn brute_force<const D: usize>(worley: &Worley, p: [f64; D]) -> (f64, f64) {
+        let (base, _) = cell(p);
+        let mut distances: Vec<f64> = (0..7usize.pow(D as u32))
+            .map(|index| {
+                let lattice: [i64; D] = array::from_fn(|i| base[i] + (index / 7usize.pow(i as u32) % 7) as i64 - 3);
+                let mut h = hash(worley.seed, lattice);
+                worley.metric.distance::<D>(array::from_fn(|i| {
+                    h = mix(h);
+                    lattice[i] as f64 + unit(h) - p[i]
+                }))
+            })
+            .collect();
+        distances.sort_by(f64::total_cmp);
+        (distances[0], distances[1])
+    }
+
+    fn nearest_two<const D: usize>(metric: Metric) {
+        let worley = |feature| Worley { seed: 5, metric, feature };
+        for p in points::<D>(2000) {
+            let (f1, f2) = (sample(&worley(Feature::F1), p), sample(&worley(Feature::F2), p));
+            assert!(f1 <= f2);
+            assert_eq!((f1, f2), brute_force(&worley(Feature::F1), p), "at {:?}", p);
+            assert_eq!(sample(&worley(Feature::F2MinusF1), p), f2 - f1);
+        }
+    }
+
+    /// A noise that is the same everywhere, to make displacements predictable.
+    struct Constant(f64);
+
+    impl Noise for Constant {
+        fn noise2(&self, _: [f64; 2]) -> f64 {
+            self.0
+        }
+
+        fn noise3(&self, _: [f64; 3]) -> f64 {
+            self.0
+        }
+
+        fn noise4(&self, _: [f64; 4]) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn one_octave_fractals_are_the_noise_itself() {
+        let fractal = |kind| Fractal {
+            octaves: 1,
+            ..Fractal::new(Perlin { seed: 2 }, kind)
+        };
+        let (fbm, turbulence, ridged) = (
+            fractal(FractalKind::Fbm),
+            fractal(FractalKind::Turbulence),
+            fractal(FractalKind::Ridged),
+        );
+        for p in points::<3>(500) {
+            let n = sample(&Perlin { seed: 2 }, p);
+            assert_eq!(sample(&fbm, p), n);
+            // Folding rescales the value through the noise's range, with some rounding.
+            assert!((sample(&turbulence, p) - n.abs()).abs() < 1e-12);
+            assert!((sample(&ridged, p) - (1.0 - n.abs()).powi(2)).abs() < 1e-12);
+        }
+        assert_eq!(turbulence.range(), (0.0, 1.0));
+        assert_eq!(fbm.range(), (-1.0, 1.0));
+    }
+
+    #[test]
+    fn octaves_are_weighted_by_gain_and_scaled_by_lacunarity() {
+        // Octaves of a constant add up to the constant, whatever the gain.
+        for gain in [0.25, 0.5, 1.0] {
+            let flat = Fractal {
+                gain,
+                ..Fractal::new(Constant(0.3), FractalKind::Fbm)
+            };
+            assert!((sample(&flat, [1.0, 2.0]) - 0.3).abs() < 1e-15);
+        }
+        // With no gain, only the first octave counts, and more octaves change nothing.
+        let noise = Perlin { seed: 4 };
+        let silent = Fractal {
+            gain: 0.0,
+            octaves: 4,
+            ..Fractal::new(noise, FractalKind::Fbm)
+        };
+        let single = Fractal { octaves: 1, ..silent };
+        assert!(points::<2>(200).iter().all(|&p| sample(&silent, p) == sample(&single, p)));
+        // Detail comes from the later octaves, so more of them change the result.
+        let detailed = Fractal {
+            octaves: 4,
+            ..Fractal::new(noise, FractalKind::Fbm)
+        };
+        assert!(points::<2>(200).iter().any(|&p| sample(&detailed, p) != sample(&single, p)));
+        // Lacunarity only scales the octaves after the first.
+        let coarse = Fractal { lacunarity: 1.5, ..detailed };
+        assert!(points::<2>(200).iter().any(|&p| sample(&detailed, p) != sample(&coarse, p)));
+        let zero = Fractal { octaves: 0, ..detailed };
+        assert_eq!(sample(&zero, [0.5, 0.5]), 0.0);
+    }
+
+    #[test]
+    fn warps_displace_the_sample_position() {
+        let noise = OpenSimplex { seed: 9 };
+        let still = Warp {
+            noise,
+            warp: OpenSimplex { seed: 10 },
+            strength: 0.0,
+        };
+        for p in points::<3>(500) {
+            assert_eq!(sample(&still, p), sample(&noise, p));
+        }
+        assert_eq!(still.range(), noise.range());
+
+        // A constant warp moves every axis by the same amount.
+        let shifted = Warp {
+            noise,
+            warp: Constant(0.5),
+            strength: 2.0,
+        };
+        for p in points::<2>(500) {
+            assert_eq!(sample(&shifted, p), sample(&noise, [p[0] + 1.0, p[1] + 1.0]));
+        }
+    }
+
+    #[test]
+    fn worley_finds_the_two_nearest_points() {
+        for metric in [Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev] {
+            nearest_two::<2>(metric);
+            nearest_two::<3>(metric);
+        }
+    }
+}
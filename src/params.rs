@@ -0,0 +1,126 @@
+//! Named generator parameters, given on the command line as `-p key=value`.
+
+use crate::error::{Error, Result};
+use crate::vec3::Vec3;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A set of `key=value` settings for a generator. Values are parsed when the
+/// generator asks for them, so each generator decides which keys it accepts.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    values: BTreeMap<String, String>,
+}
+
+impl Params {
+    pub fn new() -> Params {
+        Params::default()
+    }
+
+    /// Sets `key`, replacing any earlier value.
+    pub fn set<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    /// Adds a setting written as `key=value`.
+    pub fn insert_pair(&mut self, pair: &str) -> Result<()> {
+        match pair.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                self.set(key.trim(), value.trim());
+                Ok(())
+            }
+            _ => Err(Error::Usage(format!("invalid parameter '{}': expected key=value", pair))),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The raw value of `key`, if it was given.
+    pub fn raw(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Parses `key` with `parse`, if it was given.
+    pub fn get_with<T, F>(&self, key: &str, parse: F) -> Result<Option<T>>
+    where
+        F: FnOnce(&str) -> std::result::Result<T, String>,
+    {
+        match self.raw(key) {
+            None => Ok(None),
+            Some(value) => parse(value)
+                .map(Some)
+                .map_err(|e| Error::Usage(format!("invalid value '{}' for parameter '{}': {}", value, key, e))),
+        }
+    }
+
+    /// Parses `key` with its `FromStr` implementation, if it was given.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get_with(key, |value| value.parse().map_err(|e: T::Err| e.to_string()))
+    }
+
+    /// Parses `key`, falling back to `default` if it was not given.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+
+    /// Parses `key` as a finite number, falling back to `default` if it was not given.
+    pub fn number(&self, key: &str, default: f64) -> Result<f64> {
+        let value = self.get_with(key, |value| match value.parse::<f64>() {
+            Ok(v) if v.is_finite() => Ok(v),
+            _ => Err("expected a number".to_string()),
+        })?;
+        Ok(value.unwrap_or(default))
+    }
+
+    /// Fails on the first key that is not in `known`, so that typos do not go unnoticed.
+    pub fn check_known(&self, owner: &str, known: &[&str]) -> Result<()> {
+        match self.values.keys().find(|key| !known.contains(&key.as_str())) {
+            None => Ok(()),
+            Some(key) if known.is_empty() => {
+                Err(Error::Usage(format!("'{}' takes no parameters, but '{}' was given", owner, key)))
+            }
+            Some(key) => Err(Error::Usage(format!(
+                "unknown parameter '{}' for '{}' (expected {})",
+                key,
+                owner,
+                known.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Parses a pair of numbers written as `x,y`.
+pub fn parse_pair(s: &str) -> std::result::Result<(f64, f64), String> {
+    let parse = |v: &str| match v.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err("expected two numbers as x,y".to_string()),
+    };
+    match s.split_once(',') {
+        Some((x, y)) => Ok((parse(x)?, parse(y)?)),
+        None => Err("expected two numbers as x,y".to_string()),
+    }
+}
+
+/// Parses a vector written as `x,y,z`.
+pub fn parse_vec3(s: &str) -> std::result::Result<Vec3, String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    let numbers: Option<Vec<f64>> = parts
+        .iter()
+        .map(|v| v.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+        .collect();
+    match numbers.as_deref() {
+        Some(&[x, y, z]) => Ok(Vec3::new(x, y, z)),
+        _ => Err("expected three numbers as x,y,z".to_string()),
+    }
+}
@@ -0,0 +1,52 @@
+//! Portable Float Map (`.pfm`) encoder.
+
+use crate::canvas::Canvas;
+use std::io::{self, Write};
+
+/// Writes `canvas` as a little-endian color PFM with unclamped 32-bit samples.
+pub fn write<W: Write>(w: &mut W, canvas: &Canvas) -> io::Result<()> {
+    let width = canvas.width() as usize;
+    // A negative scale marks little-endian data.
+    write!(w, "PF\n{} {}\n-1.0\n", canvas.width(), canvas.height())?;
+
+    // Scanlines are stored bottom to top.
+    let mut line = Vec::with_capacity(width * 12);
+    for row in canvas.pixels().chunks(width).rev() {
+        line.clear();
+        for c in row {
+            for &v in &[c.r, c.g, c.b] {
+                line.extend_from_slice(&(v as f32).to_le_bytes());
+            }
+        }
+        w.write_all(&line)?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn round_trips_unclamped_samples_bottom_up() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set_pixel(0, 0, Color::new(1.5, -0.25, 1e-3));
+        canvas.set_pixel(1, 1, Color::new(0.1, 100.0, 0.5));
+        let mut bytes = Vec::new();
+        write(&mut bytes, &canvas).unwrap();
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(floats.len(), 12);
+        for (i, rgb) in floats.chunks(3).enumerate() {
+            let (x, y) = (i as u32 % 2, 1 - i as u32 / 2);
+            let c = canvas.pixel(x, y);
+            assert_eq!(rgb, [c.r as f32, c.g as f32, c.b as f32]);
+        }
+    }
+}
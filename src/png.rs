@@ -0,0 +1,226 @@
+//! PNG output with color space metadata.
+//!
+//! The pixel data is encoded by the `image` crate; this module splices in the
+//! ancillary chunks that tell viewers how the samples were encoded.
+
+use crate::transfer::Transfer;
+use image::png::PNGEncoder;
+use image::{ColorType, ImageEncoder, ImageResult};
+use std::io::{self, Write};
+
+// The sRGB/BT.709 white point and primaries, in units of 1/100000.
+const SRGB_CHROMATICITIES: [u32; 8] = [31270, 32900, 64000, 33000, 30000, 60000, 15000, 6000];
+
+/// Encodes `data` as a PNG and writes it to `w` along with `gAMA`, `cHRM` and either
+/// `sRGB` or `iCCP` chunks describing `transfer`. Pass-through output is untagged.
+///
+/// 16-bit samples are expected in native byte order.
+pub fn write<W: Write>(
+    w: &mut W,
+    data: &[u8],
+    width: u32,
+    height: u32,
+    color: ColorType,
+    transfer: Transfer,
+) -> ImageResult<()> {
+    let mut png = Vec::new();
+    PNGEncoder::new(&mut png).write_image(data, width, height, color)?;
+
+    // The signature is 8 bytes and IHDR is always first: 4 length + 4 type + 13 data
+    // + 4 CRC. Color space chunks must come before PLTE and IDAT.
+    let (head, rest) = png.split_at(8 + 25);
+    w.write_all(head)?;
+    for (name, data) in color_chunks(transfer) {
+        write_chunk(w, name, &data)?;
+    }
+    w.write_all(rest)?;
+    w.flush()?;
+    Ok(())
+}
+
+fn color_chunks(transfer: Transfer) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    let gamma = match transfer.decoding_gamma() {
+        Some(gamma) => (100_000.0 / gamma).round() as u32,
+        None => return Vec::new(),
+    };
+    let mut chunks = vec![
+        (b"gAMA", gamma.to_be_bytes().to_vec()),
+        (b"cHRM", SRGB_CHROMATICITIES.iter().flat_map(|v| v.to_be_bytes().to_vec()).collect()),
+    ];
+    match transfer {
+        // Rendering intent 0: perceptual. sRGB and iCCP are mutually exclusive.
+        Transfer::Srgb => chunks.push((b"sRGB", vec![0])),
+        Transfer::PassThrough => {}
+        Transfer::Gamma(_) => {
+            // Profile name, null separator and compression method 0 (zlib).
+            let mut data = profile_name(transfer).into_bytes();
+            data.extend_from_slice(&[0, 0]);
+            data.extend(deflate::deflate_bytes_zlib(&icc_profile(transfer)));
+            chunks.push((b"iCCP", data));
+        }
+    }
+    chunks
+}
+
+fn profile_name(transfer: Transfer) -> String {
+    match transfer {
+        Transfer::Gamma(gamma) if gamma != 1.0 => format!("Gamma {:.2} RGB", gamma),
+        _ => "Linear RGB".to_string(),
+    }
+}
+
+fn write_chunk<W: Write>(w: &mut W, name: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(name)?;
+    w.write_all(data)?;
+    let crc = crc32(name.iter().chain(data));
+    w.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Builds a minimal ICC v2 display profile with sRGB primaries and the pure power law
+/// tone curve of a [`Transfer::Gamma`].
+fn icc_profile(transfer: Transfer) -> Vec<u8> {
+    fn s15f16(v: f64) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for &v in &[x, y, z] {
+            tag.extend_from_slice(&s15f16(v));
+        }
+        tag
+    }
+
+    let description = profile_name(transfer);
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(description.len() as u32 + 1).to_be_bytes());
+    desc.extend_from_slice(description.as_bytes());
+    desc.push(0);
+    // Empty Unicode and ScriptCode descriptions.
+    desc.extend_from_slice(&[0; 8]);
+    desc.extend_from_slice(&[0; 3 + 67]);
+
+    // A curve with no entries is the identity; one entry is a u8.8 gamma.
+    let curve = match transfer {
+        Transfer::Gamma(gamma) if gamma != 1.0 => {
+            let mut curve = b"curv\0\0\0\0\0\0\0\x01".to_vec();
+            curve.extend_from_slice(&((gamma * 256.0).round() as u16).to_be_bytes());
+            curve
+        }
+        _ => b"curv\0\0\0\0\0\0\0\0".to_vec(),
+    };
+
+    // Colorants are the sRGB primaries chromatically adapted to D50.
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", desc),
+        (b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+        (b"rXYZ", xyz(0.436_074_7, 0.222_504_5, 0.013_932_2)),
+        (b"gXYZ", xyz(0.385_064_9, 0.716_878_6, 0.097_104_5)),
+        (b"bXYZ", xyz(0.143_080_4, 0.060_616_9, 0.714_173_3)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+        (b"cprt", b"text\0\0\0\0No copyright\0".to_vec()),
+    ];
+
+    let mut table = Vec::new();
+    let mut body = Vec::new();
+    let mut offset = 128 + 4 + 12 * tags.len();
+    for (signature, data) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        body.extend_from_slice(data);
+        // Tags start on 4 byte boundaries.
+        while body.len() % 4 != 0 {
+            body.push(0);
+        }
+        offset = 128 + 4 + 12 * tags.len() + body.len();
+    }
+
+    let size = 128 + 4 + table.len() + body.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]); // preferred CMM
+    profile.extend_from_slice(&[2, 0x10, 0, 0]); // version 2.1
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    profile.extend_from_slice(&[0; 12]); // creation date
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 24]); // platform, flags, manufacturer, model, attributes
+    profile.extend_from_slice(&[0; 4]); // perceptual rendering intent
+    profile.extend_from_slice(&s15f16(0.9642));
+    profile.extend_from_slice(&s15f16(1.0));
+    profile.extend_from_slice(&s15f16(0.8249));
+    profile.resize(128, 0); // creator, profile ID and reserved bytes
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    profile.extend(table);
+    profile.extend(body);
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The chunks of an encoded 1x1 image, between IHDR and IDAT.
+    fn color_chunk_bytes(transfer: Transfer) -> Vec<u8> {
+        let mut png = Vec::new();
+        write(&mut png, &[255, 128, 0], 1, 1, ColorType::Rgb8, transfer).unwrap();
+        // The file must still decode, which also checks every chunk's CRC.
+        let decoded = image::load_from_memory(&png).unwrap().to_rgb();
+        assert_eq!(decoded.get_pixel(0, 0).0, [255, 128, 0]);
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap() - 4;
+        png[8 + 25..idat].to_vec()
+    }
+
+    #[test]
+    fn crc_matches_known_chunks() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn srgb_output_is_tagged_with_standard_chunks() {
+        let mut expected = b"\0\0\0\x04gAMA\0\0\xb1\x8f\x0b\xfc\x61\x05".to_vec();
+        expected.extend_from_slice(b"\0\0\0\x20cHRM\0\0\x7a\x26\0\0\x80\x84\0\0\xfa\0\0\0\x80\xe8");
+        expected.extend_from_slice(b"\0\0\x75\x30\0\0\xea\x60\0\0\x3a\x98\0\0\x17\x70\x9c\xba\x51\x3c");
+        expected.extend_from_slice(b"\0\0\0\x01sRGB\0\xae\xce\x1c\xe9");
+        assert_eq!(color_chunk_bytes(Transfer::Srgb), expected);
+        assert!(color_chunk_bytes(Transfer::PassThrough).is_empty());
+    }
+
+    #[test]
+    fn gamma_output_embeds_a_compressed_profile() {
+        let chunks = color_chunk_bytes(Transfer::Gamma(1.8));
+        let iccp = chunks.windows(4).position(|w| w == b"iCCP").unwrap();
+        let len = u32::from_be_bytes([chunks[iccp - 4], chunks[iccp - 3], chunks[iccp - 2], chunks[iccp - 1]]);
+        let data = &chunks[iccp + 4..iccp + 4 + len as usize];
+        assert!(data.starts_with(b"Gamma 1.80 RGB\0\0\x78"));
+        assert_eq!(chunks.len(), iccp + 4 + len as usize + 4);
+        assert!(!chunks.windows(4).any(|w| w == b"sRGB"));
+    }
+
+    #[test]
+    fn icc_profile_header_is_well_formed() {
+        let profile = icc_profile(Transfer::Gamma(2.2));
+        assert_eq!(profile[..4], (profile.len() as u32).to_be_bytes());
+        assert_eq!(&profile[8..24], b"\x02\x10\0\0mntrRGB XYZ ");
+        assert_eq!(&profile[36..40], b"acsp");
+        // The D50 illuminant, then the tag count.
+        assert_eq!(profile[68..80], [0, 0, 0xf6, 0xd6, 0, 1, 0, 0, 0, 0, 0xd3, 0x2d]);
+        assert_eq!(profile[128..132], 9u32.to_be_bytes());
+        // The tone curve is a u8.8 gamma: 2.2 * 256 rounds to 563.
+        let curve = profile.windows(12).position(|w| w == b"curv\0\0\0\0\0\0\0\x01").unwrap();
+        assert_eq!(profile[curve + 12..curve + 14], 563u16.to_be_bytes());
+    }
+}
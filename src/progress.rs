@@ -0,0 +1,335 @@
+//! Progress reporting for long renders.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Receives progress updates from the renderer. Work is counted in pixels, and
+/// `advance` is called concurrently from the render threads.
+pub trait Progress: Sync {
+    fn start(&self, _total: u64) {}
+    fn advance(&self, _done: u64) {}
+    fn finish(&self) {}
+}
+
+/// Reports nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quiet;
+
+impl Progress for Quiet {}
+
+/// A point-in-time view of a render's progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    pub done: u64,
+    pub total: u64,
+    pub elapsed: Duration,
+}
+
+impl Snapshot {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+
+    pub fn pixels_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.done as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated time left at the average rate so far, once there is a rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.pixels_per_second();
+        if rate > 0.0 {
+            Some(Duration::from_secs_f64(self.total.saturating_sub(self.done) as f64 / rate))
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts finished work and decides when the next report is due.
+struct Tracker {
+    interval: Duration,
+    total: AtomicU64,
+    done: AtomicU64,
+    started: Mutex<Instant>,
+    last_report: Mutex<Instant>,
+}
+
+impl Tracker {
+    fn new(interval: Duration) -> Tracker {
+        let now = Instant::now();
+        Tracker {
+            interval,
+            total: AtomicU64::new(0),
+            done: AtomicU64::new(0),
+            started: Mutex::new(now),
+            last_report: Mutex::new(now),
+        }
+    }
+
+    fn start(&self, total: u64) -> Snapshot {
+        let now = Instant::now();
+        self.total.store(total, Ordering::SeqCst);
+        self.done.store(0, Ordering::SeqCst);
+        *self.started.lock().unwrap() = now;
+        *self.last_report.lock().unwrap() = now;
+        self.snapshot()
+    }
+
+    /// Records `n` more finished pixels and returns a snapshot if a report is due.
+    /// Threads that find another one reporting skip it rather than wait.
+    fn advance(&self, n: u64) -> Option<Snapshot> {
+        self.done.fetch_add(n, Ordering::SeqCst);
+        let mut last = self.last_report.try_lock().ok()?;
+        let now = Instant::now();
+        if now.duration_since(*last) < self.interval {
+            return None;
+        }
+        *last = now;
+        Some(self.snapshot())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            done: self.done.load(Ordering::SeqCst),
+            total: self.total.load(Ordering::SeqCst),
+            elapsed: self.started.lock().unwrap().elapsed(),
+        }
+    }
+}
+
+/// A single-line progress bar with throughput and ETA, redrawn in place on stderr.
+pub struct Bar {
+    tracker: Tracker,
+}
+
+impl Default for Bar {
+    fn default() -> Self {
+        Bar {
+            tracker: Tracker::new(Duration::from_millis(100)),
+        }
+    }
+}
+
+impl Bar {
+    const WIDTH: usize = 30;
+
+    fn draw(&self, snapshot: Snapshot) {
+        let filled = (snapshot.fraction() * Self::WIDTH as f64) as usize;
+        let eta = match snapshot.eta() {
+            Some(eta) => format_duration(eta),
+            None => "--:--".to_string(),
+        };
+        // Errors writing to stderr are not worth failing a render over.
+        let _ = write!(
+            io::stderr().lock(),
+            "\r[{}{}] {:5.1}%  {}  ETA {}   ",
+            "#".repeat(filled),
+            "-".repeat(Self::WIDTH - filled.min(Self::WIDTH)),
+            snapshot.fraction() * 100.0,
+            format_rate(snapshot.pixels_per_second()),
+            eta
+        );
+    }
+}
+
+impl Progress for Bar {
+    fn start(&self, total: u64) {
+        self.draw(self.tracker.start(total));
+    }
+
+    fn advance(&self, done: u64) {
+        if let Some(snapshot) = self.tracker.advance(done) {
+            self.draw(snapshot);
+        }
+    }
+
+    fn finish(&self) {
+        let snapshot = self.tracker.snapshot();
+        self.draw(snapshot);
+        let _ = writeln!(
+            io::stderr().lock(),
+            "\nRendered {} pixels in {}",
+            snapshot.done,
+            format_duration(snapshot.elapsed)
+        );
+    }
+}
+
+/// Machine-readable progress: one JSON object per line on stderr.
+///
+/// Every line has the same fields, whatever its `event`: `start`, `progress` or
+/// `finish`. They are the `done` and `total` pixel counts, the `fraction` done,
+/// `elapsed` seconds, `pixels_per_sec` and `eta` seconds, which is `null` until a
+/// rate is known.
+pub struct JsonLines {
+    tracker: Tracker,
+}
+
+impl Default for JsonLines {
+    fn default() -> Self {
+        JsonLines {
+            tracker: Tracker::new(Duration::from_millis(500)),
+        }
+    }
+}
+
+impl JsonLines {
+    fn line(event: &str, snapshot: Snapshot) -> String {
+        let eta = match snapshot.eta() {
+            Some(eta) => format!("{:.3}", eta.as_secs_f64()),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"event\":\"{}\",\"done\":{},\"total\":{},\"fraction\":{:.6},\"elapsed\":{:.3},\"pixels_per_sec\":{:.1},\"eta\":{}}}",
+            event,
+            snapshot.done,
+            snapshot.total,
+            snapshot.fraction(),
+            snapshot.elapsed.as_secs_f64(),
+            snapshot.pixels_per_second(),
+            eta
+        )
+    }
+
+    fn emit(&self, event: &str, snapshot: Snapshot) {
+        let _ = writeln!(io::stderr().lock(), "{}", Self::line(event, snapshot));
+    }
+}
+
+impl Progress for JsonLines {
+    fn start(&self, total: u64) {
+        self.emit("start", self.tracker.start(total));
+    }
+
+    fn advance(&self, done: u64) {
+        if let Some(snapshot) = self.tracker.advance(done) {
+            self.emit("progress", snapshot);
+        }
+    }
+
+    fn finish(&self) {
+        self.emit("finish", self.tracker.snapshot());
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn format_rate(pixels_per_second: f64) -> String {
+    if pixels_per_second >= 1e6 {
+        format!("{:.2} Mpx/s", pixels_per_second / 1e6)
+    } else if pixels_per_second >= 1e3 {
+        format!("{:.1} kpx/s", pixels_per_second / 1e3)
+    } else {
+        format!("{:.0} px/s", pixels_per_second)
+    }
+}
+
+/// The reporting styles selectable from the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    None,
+    Bar,
+    Json,
+}
+
+impl Style {
+    pub fn reporter(self) -> Box<dyn Progress> {
+        match self {
+            Style::None => Box::new(Quiet),
+            Style::Bar => Box::new(Bar::default()),
+            Style::Json => Box::new(JsonLines::default()),
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Style::None),
+            "bar" => Ok(Style::Bar),
+            "json" => Ok(Style::Json),
+            _ => Err(format!("unknown progress style '{}' (expected none, bar or json)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(done: u64, total: u64, millis: u64) -> Snapshot {
+        Snapshot {
+            done,
+            total,
+            elapsed: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn snapshots_estimate_the_time_left() {
+        let quarter = snapshot(250, 1000, 2000);
+        assert_eq!(quarter.fraction(), 0.25);
+        assert_eq!(quarter.pixels_per_second(), 125.0);
+        assert_eq!(quarter.eta(), Some(Duration::from_secs(6)));
+
+        // No rate yet, and nothing to do.
+        assert_eq!(snapshot(0, 1000, 0).eta(), None);
+        assert_eq!(snapshot(0, 1000, 500).eta(), None);
+        assert_eq!(snapshot(0, 0, 0).fraction(), 1.0);
+        assert_eq!(snapshot(1000, 1000, 10).eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn json_lines_have_a_fixed_format() {
+        assert_eq!(
+            JsonLines::line("progress", snapshot(250, 1000, 2000)),
+            "{\"event\":\"progress\",\"done\":250,\"total\":1000,\"fraction\":0.250000,\"elapsed\":2.000,\
+             \"pixels_per_sec\":125.0,\"eta\":6.000}"
+        );
+        assert_eq!(
+            JsonLines::line("start", snapshot(0, 64, 0)),
+            "{\"event\":\"start\",\"done\":0,\"total\":64,\"fraction\":0.000000,\"elapsed\":0.000,\
+             \"pixels_per_sec\":0.0,\"eta\":null}"
+        );
+
+        // Consumers can rely on one schema for every event.
+        let keys = |line: String| -> Vec<String> {
+            line.split(',').map(|field| field.split(':').next().unwrap().trim_matches('{').to_string()).collect()
+        };
+        let start = keys(JsonLines::line("start", snapshot(0, 64, 0)));
+        assert_eq!(start.len(), 7);
+        assert!(start.contains(&"\"fraction\"".to_string()) && start.contains(&"\"eta\"".to_string()));
+        assert_eq!(keys(JsonLines::line("progress", snapshot(16, 64, 100))), start);
+        assert_eq!(keys(JsonLines::line("finish", snapshot(64, 64, 400))), start);
+    }
+
+    #[test]
+    fn durations_and_rates_are_human_readable() {
+        assert_eq!(format_duration(Duration::from_secs(75)), "01:15");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 61)), "3:01:01");
+        assert_eq!(format_rate(512.0), "512 px/s");
+        assert_eq!(format_rate(2500.0), "2.5 kpx/s");
+        assert_eq!(format_rate(3.25e6), "3.25 Mpx/s");
+    }
+}
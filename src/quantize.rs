@@ -0,0 +1,317 @@
+//! Conversion of the floating point canvas into integer samples.
+
+use crate::color::Color;
+use crate::dither::{Dither, Palette};
+use crate::transfer::Transfer;
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::str::FromStr;
+
+/// An RGB image with 16 bits per channel.
+pub type Rgb16Image = ImageBuffer<Rgb<u16>, Vec<u16>>;
+
+/// How out-of-range colors are brought into `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clamp {
+    /// Clamp each channel separately. Bright saturated colors drift towards white.
+    Clip,
+    /// Scale the whole color down by its largest channel, keeping its hue, and then
+    /// clip any negative channels.
+    ScaleToFit,
+}
+
+/// How a value in `0.0..=1.0` is mapped to an integer level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    /// `floor(v * (max + 0.999))`: every level covers an equal share of the input
+    /// range. This is the mapping used by the book.
+    Floor,
+    /// `round(v * max)`: levels sit exactly on multiples of `1 / max`.
+    Nearest,
+}
+
+/// The quantization stage applied when a canvas is saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantizer {
+    pub clamp: Clamp,
+    pub rounding: Rounding,
+    /// Applied to the clamped linear values before they are mapped to levels.
+    pub transfer: Transfer,
+    /// Significant bits per channel, `1..=16`. Levels are scaled up to the full
+    /// range of the output samples so that they can be viewed as usual. Depths above
+    /// 8 bits are written as 16-bit samples where the format allows it.
+    pub bits: u8,
+    /// Dithering replaces `rounding` with thresholds or error diffusion.
+    pub dither: Dither,
+    /// Restricts the output to these colors instead of every level at `bits`.
+    pub palette: Option<Palette>,
+}
+
+impl Default for Quantizer {
+    fn default() -> Self {
+        Quantizer {
+            clamp: Clamp::Clip,
+            rounding: Rounding::Floor,
+            transfer: Transfer::PassThrough,
+            bits: 8,
+            dither: Dither::None,
+            palette: None,
+        }
+    }
+}
+
+impl Quantizer {
+    pub const MAX_BITS: u8 = 16;
+
+    /// Whether the configured bit depth needs 16-bit samples.
+    pub fn is_wide(&self) -> bool {
+        self.bits > 8
+    }
+
+    /// The highest level at the configured bit depth.
+    pub fn max_level(&self) -> u32 {
+        (1 << self.bits) - 1
+    }
+
+    /// Brings `color` into `0.0..=1.0` according to the clamp mode. NaN becomes zero.
+    pub fn clamp(&self, color: Color) -> Color {
+        let color = match self.clamp {
+            Clamp::Clip => color,
+            Clamp::ScaleToFit => {
+                let max = color.max_channel();
+                if max > 1.0 {
+                    color / max
+                } else {
+                    color
+                }
+            }
+        };
+        let clip = |v: f64| if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+        Color::new(clip(color.r), clip(color.g), clip(color.b))
+    }
+
+    /// Maps a channel value in `0.0..=1.0` to a level in `0..=max_level()`.
+    pub fn level(&self, v: f64) -> u32 {
+        let max = self.max_level() as f64;
+        let level = match self.rounding {
+            Rounding::Floor => (v * (max + 0.999)).floor(),
+            Rounding::Nearest => (v * max).round(),
+        };
+        level.clamp(0.0, max) as u32
+    }
+
+    /// Clamps `color` and applies the transfer function.
+    pub fn encode(&self, color: Color) -> Color {
+        let c = self.clamp(color);
+        Color::new(self.transfer.encode(c.r), self.transfer.encode(c.g), self.transfer.encode(c.b))
+    }
+
+    fn nearest_level(&self, v: f64) -> u32 {
+        let max = self.max_level() as f64;
+        (v * max).round().clamp(0.0, max) as u32
+    }
+
+    fn nearest_levels(&self, c: Color) -> [u32; 3] {
+        [self.nearest_level(c.r), self.nearest_level(c.g), self.nearest_level(c.b)]
+    }
+
+    /// Quantizes a row-major slice of `width` x `height` colors to a level per channel,
+    /// applying the dither and palette.
+    pub fn quantize(&self, width: u32, height: u32, pixels: &[Color]) -> Vec<[u32; 3]> {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        let encoded: Vec<Color> = pixels.iter().map(|&c| self.encode(c)).collect();
+        let position = |i: usize| ((i % width as usize) as u32, (i / width as usize) as u32);
+
+        if let Some((taps, divisor)) = self.dither.kernel() {
+            return self.diffuse(width as usize, height as usize, encoded, taps, divisor);
+        }
+
+        encoded
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                let (x, y) = position(i);
+                match (self.dither.threshold(x, y), &self.palette) {
+                    (None, None) => [self.level(c.r), self.level(c.g), self.level(c.b)],
+                    (None, Some(palette)) => self.nearest_levels(palette.nearest(c)),
+                    (Some(t), None) => {
+                        let max = self.max_level() as f64;
+                        let dithered = |v: f64| (v * max + t).floor().clamp(0.0, max) as u32;
+                        [dithered(c.r), dithered(c.g), dithered(c.b)]
+                    }
+                    (Some(t), Some(palette)) => {
+                        let nudge = (t - 0.5) * palette.spread();
+                        let c = Color::new(c.r + nudge, c.g + nudge, c.b + nudge);
+                        self.nearest_levels(palette.nearest(c))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Error diffusion in serpentine order, which avoids the diagonal worms that
+    /// strictly left-to-right scanning produces.
+    fn diffuse(
+        &self,
+        width: usize,
+        height: usize,
+        mut work: Vec<Color>,
+        taps: &[(i32, i32, f64)],
+        divisor: f64,
+    ) -> Vec<[u32; 3]> {
+        let max = self.max_level() as f64;
+        let mut levels = vec![[0; 3]; work.len()];
+
+        for y in 0..height {
+            let reverse = y % 2 == 1;
+            for i in 0..width {
+                let x = if reverse { width - 1 - i } else { i };
+                let index = y * width + x;
+                let wanted = work[index];
+
+                let (level, chosen) = match &self.palette {
+                    Some(palette) => {
+                        let chosen = palette.nearest(wanted);
+                        (self.nearest_levels(chosen), chosen)
+                    }
+                    None => {
+                        let level = self.nearest_levels(wanted);
+                        let value = |l: u32| l as f64 / max;
+                        (level, Color::new(value(level[0]), value(level[1]), value(level[2])))
+                    }
+                };
+                levels[index] = level;
+
+                let error = Color::new(wanted.r - chosen.r, wanted.g - chosen.g, wanted.b - chosen.b);
+                for &(dx, dy, weight) in taps {
+                    let dx = if reverse { -dx } else { dx };
+                    let (tx, ty) = (x as i32 + dx, y + dy as usize);
+                    if tx < 0 || tx >= width as i32 || ty >= height {
+                        continue;
+                    }
+                    work[ty * width + tx as usize] += error * (weight / divisor);
+                }
+            }
+        }
+        levels
+    }
+
+    /// Rescales a level to the range `0..=target_max`, rounding to nearest.
+    fn expand(&self, level: u32, target_max: u32) -> u32 {
+        let max = self.max_level() as u64;
+        ((level as u64 * target_max as u64 + max / 2) / max) as u32
+    }
+
+    /// Quantizes a row-major slice of `width` x `height` colors to 8 bits per channel.
+    /// Depths above 8 bits are quantized exactly as 8 bits would be, rather than
+    /// rounding the wider levels down.
+    pub fn to_rgb8(&self, width: u32, height: u32, pixels: &[Color]) -> RgbImage {
+        if self.is_wide() {
+            let narrow = Quantizer {
+                bits: 8,
+                ..self.clone()
+            };
+            return narrow.to_rgb8(width, height, pixels);
+        }
+        let mut image = RgbImage::new(width, height);
+        for (pixel, [r, g, b]) in image.pixels_mut().zip(self.quantize(width, height, pixels)) {
+            *pixel = Rgb([self.expand(r, 255) as u8, self.expand(g, 255) as u8, self.expand(b, 255) as u8]);
+        }
+        image
+    }
+
+    /// Quantizes a row-major slice of `width` x `height` colors to 16 bits per channel.
+    pub fn to_rgb16(&self, width: u32, height: u32, pixels: &[Color]) -> Rgb16Image {
+        let mut image = Rgb16Image::new(width, height);
+        for (pixel, [r, g, b]) in image.pixels_mut().zip(self.quantize(width, height, pixels)) {
+            *pixel = Rgb([
+                self.expand(r, 65535) as u16,
+                self.expand(g, 65535) as u16,
+                self.expand(b, 65535) as u16,
+            ]);
+        }
+        image
+    }
+}
+
+impl FromStr for Clamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => Ok(Clamp::Clip),
+            "scale" => Ok(Clamp::ScaleToFit),
+            _ => Err(format!("unknown clamp mode '{}' (expected clip or scale)", s)),
+        }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "floor" => Ok(Rounding::Floor),
+            "nearest" => Ok(Rounding::Nearest),
+            _ => Err(format!("unknown rounding mode '{}' (expected floor or nearest)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray_ramp(n: usize) -> Vec<Color> {
+        (0..n).map(|i| Color::gray(i as f64 / (n - 1) as f64)).collect()
+    }
+
+    #[test]
+    fn floor_rounding_matches_the_book_at_eight_bits() {
+        let quantizer = Quantizer::default();
+        for v in gray_ramp(4097).iter().map(|c| c.r) {
+            assert_eq!(quantizer.level(v), (v * 255.999) as u32, "{}", v);
+        }
+        assert_eq!(quantizer.level(1.0), 255);
+        assert_eq!(quantizer.level(1.0 - 1e-9), 255);
+    }
+
+    #[test]
+    fn wide_depths_reduce_to_the_same_eight_bit_image() {
+        let pixels = gray_ramp(1000);
+        let eight = Quantizer::default();
+        for &rounding in &[Rounding::Floor, Rounding::Nearest] {
+            for &bits in &[10, 12, 16] {
+                let wide = Quantizer {
+                    rounding,
+                    bits,
+                    ..Quantizer::default()
+                };
+                let narrow = Quantizer { rounding, ..eight.clone() };
+                assert_eq!(wide.to_rgb8(1000, 1, &pixels), narrow.to_rgb8(1000, 1, &pixels), "{}", bits);
+            }
+        }
+    }
+
+    #[test]
+    fn levels_expand_to_the_full_sample_range() {
+        let pixels = [Color::BLACK, Color::gray(0.5), Color::WHITE];
+        let sixteen = Quantizer {
+            bits: 16,
+            ..Quantizer::default()
+        };
+        let wide = sixteen.to_rgb16(3, 1, &pixels);
+        assert_eq!(wide.get_pixel(0, 0).0, [0; 3]);
+        assert_eq!(wide.get_pixel(1, 0).0, [32767; 3]);
+        assert_eq!(wide.get_pixel(2, 0).0, [65535; 3]);
+
+        // Lower depths are scaled up so that their top level is still full white.
+        for &bits in &[1, 4, 8] {
+            let quantizer = Quantizer {
+                bits,
+                ..Quantizer::default()
+            };
+            assert_eq!(quantizer.to_rgb16(3, 1, &pixels).get_pixel(2, 0).0, [65535; 3]);
+            assert_eq!(quantizer.to_rgb8(3, 1, &pixels).get_pixel(2, 0).0, [255; 3]);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Radiance RGBE (`.hdr`) encoder.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io::{self, Write};
+
+/// Writes `canvas` as a run-length encoded Radiance picture. Negative values are
+/// clamped to zero since RGBE cannot represent them.
+pub fn write<W: Write>(w: &mut W, canvas: &Canvas) -> io::Result<()> {
+    let (width, height) = (canvas.width() as usize, canvas.height() as usize);
+    write!(
+        w,
+        "#?RADIANCE\nSOFTWARE=output-image\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let mut plane = Vec::with_capacity(width);
+    for row in canvas.pixels().chunks(width) {
+        let rgbe: Vec<[u8; 4]> = row.iter().map(|&c| to_rgbe(c)).collect();
+
+        // The RLE scheme can only describe scanlines of this length; anything else is
+        // written flat.
+        if !(8..0x8000).contains(&width) {
+            for pixel in &rgbe {
+                w.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        w.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+        for i in 0..4 {
+            plane.clear();
+            plane.extend(rgbe.iter().map(|p| p[i]));
+            write_rle(w, &plane)?;
+        }
+    }
+    w.flush()
+}
+
+fn to_rgbe(c: Color) -> [u8; 4] {
+    let (r, g, b) = (c.r.max(0.0), c.g.max(0.0), c.b.max(0.0));
+    let v = r.max(g).max(b);
+    if v.is_nan() || v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e with m in [0.5, 1). An infinite channel saturates the cast, and
+    // the clamp then leaves it at the largest value RGBE can hold.
+    let e = (v.log2().floor() as i32).saturating_add(1);
+    let e = e.clamp(-128, 127);
+    let scale = 256.0 / 2f64.powi(e);
+    let channel = |x: f64| (x * scale).min(255.0) as u8;
+    [channel(r), channel(g), channel(b), (e + 128) as u8]
+}
+
+/// Encodes one component plane: a count byte above 128 introduces a run of
+/// `count - 128` copies of the following byte, otherwise `count` literal bytes follow.
+fn write_rle<W: Write>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    let mut i = 0;
+    while i < data.len() {
+        // Find the next run long enough to be worth encoding.
+        let mut run_start = i;
+        let mut run_len = 0;
+        while run_start < data.len() {
+            run_len = 1;
+            while run_start + run_len < data.len() && run_len < 127 && data[run_start + run_len] == data[run_start] {
+                run_len += 1;
+            }
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+        }
+
+        // Literals up to the run, at most 128 at a time.
+        while i < run_start {
+            let n = (run_start - i).min(128);
+            w.write_all(&[n as u8])?;
+            w.write_all(&data[i..i + n])?;
+            i += n;
+        }
+
+        if run_len >= MIN_RUN {
+            w.write_all(&[128 + run_len as u8, data[run_start]])?;
+            i = run_start + run_len;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::hdr::HdrDecoder;
+
+    fn round_trip(canvas: &Canvas) -> Vec<image::Rgb<f32>> {
+        let mut bytes = Vec::new();
+        write(&mut bytes, canvas).unwrap();
+        let decoder = HdrDecoder::new(&bytes[..]).unwrap();
+        let metadata = decoder.metadata();
+        assert_eq!((metadata.width, metadata.height), (canvas.width(), canvas.height()));
+        decoder.read_image_hdr().unwrap()
+    }
+
+    fn assert_close(canvas: &Canvas, decoded: &[image::Rgb<f32>]) {
+        for (c, d) in canvas.pixels().iter().zip(decoded) {
+            // RGBE keeps 8 bits of mantissa relative to the brightest channel.
+            let tolerance = c.r.max(c.g).max(c.b) / 128.0;
+            for (&expected, &actual) in [c.r.max(0.0), c.g.max(0.0), c.b.max(0.0)].iter().zip(&d.0) {
+                assert!((expected - actual as f64).abs() <= tolerance, "{:?} vs {:?}", c, d);
+            }
+        }
+    }
+
+    #[test]
+    fn run_length_encoded_scanlines_decode() {
+        // Long enough for RLE, with runs of equal pixels and literal stretches.
+        let mut canvas = Canvas::new(40, 3);
+        canvas.fill(|x, y| match x {
+            0..=15 => Color::new(2.0, 0.5, 0.25),
+            _ => Color::new(x as f64 * 0.37, y as f64 * 12.0, -1.0),
+        });
+        assert_close(&canvas, &round_trip(&canvas));
+    }
+
+    #[test]
+    fn narrow_scanlines_are_written_flat() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.fill(|x, y| Color::new(x as f64, y as f64 * 1000.0, 1e-3));
+        assert_close(&canvas, &round_trip(&canvas));
+    }
+
+    #[test]
+    fn infinite_values_saturate() {
+        let mut canvas = Canvas::new(16, 2);
+        canvas.fill(|x, y| if (x, y) == (0, 0) { Color::new(f64::INFINITY, 1.0, 0.0) } else { Color::gray(0.5) });
+        let decoded = round_trip(&canvas);
+        // The largest RGBE value, with the finite channels too small to register.
+        assert!(decoded[0].0[0] > 1e38, "{:?}", decoded[0]);
+        assert_eq!(&decoded[0].0[1..], &[0.0, 0.0]);
+        assert_eq!(to_rgbe(Color::new(f64::INFINITY, 0.0, 0.0)), [255, 0, 0, 255]);
+        // The rest of the picture is unaffected.
+        assert!(decoded[1..].iter().flat_map(|d| &d.0).all(|&v| (v - 0.5).abs() <= 0.5 / 128.0));
+    }
+}
@@ -0,0 +1,21 @@
+//! Rays: half-lines from an origin in a direction.
+
+use crate::vec3::{Point3, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Point3,
+    /// Not necessarily of unit length.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Point3, direction: Vec3) -> Ray {
+        Ray { origin, direction }
+    }
+
+    /// The point `t` direction lengths along the ray.
+    pub fn at(&self, t: f64) -> Point3 {
+        self.origin + self.direction * t
+    }
+}
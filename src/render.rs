@@ -0,0 +1,170 @@
+use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::progress::{Progress, Quiet};
+use crate::sampling::Sampling;
+use crate::shader::{ShadeContext, Shader};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::io;
+
+/// Drives a shader over every pixel of a canvas.
+///
+/// Scanlines are shaded in parallel. Every pixel is computed independently, so the
+/// result is bit-identical whatever the number of threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Renderer {
+    pub width: u32,
+    pub height: u32,
+    /// Passed to the shader as [`ShadeContext::time`].
+    pub time: f64,
+    /// Number of worker threads, or `None` to use every core.
+    pub threads: Option<usize>,
+    pub sampling: Sampling,
+}
+
+impl Renderer {
+    pub fn new(width: u32, height: u32) -> Renderer {
+        Renderer {
+            width,
+            height,
+            time: 0.0,
+            threads: None,
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// The context for the center of pixel `(x, y)`.
+    pub fn context(&self, x: u32, y: u32) -> ShadeContext {
+        self.sample_context(x, y, 0, (0.0, 0.0))
+    }
+
+    /// The context for a sample at `(dx, dy)` pixels from the center of pixel `(x, y)`.
+    pub fn sample_context(&self, x: u32, y: u32, sample: u32, (dx, dy): (f64, f64)) -> ShadeContext {
+        ShadeContext {
+            time: self.time,
+            sample,
+            ..ShadeContext::new(x as f64 + dx, y as f64 + dy, self.width, self.height)
+        }
+    }
+
+    /// Shades every pixel. Fails only if a dedicated pool of `threads` workers
+    /// cannot be started.
+    pub fn render<S: Shader + ?Sized>(&self, shader: &S) -> Result<Canvas> {
+        self.render_with_progress(shader, &Quiet)
+    }
+
+    /// Renders like [`render`](Renderer::render), reporting each finished scanline.
+    pub fn render_with_progress<S, P>(&self, shader: &S, progress: &P) -> Result<Canvas>
+    where
+        S: Shader + ?Sized,
+        P: Progress + ?Sized,
+    {
+        // A dedicated pool only when asked for; otherwise share rayon's global one.
+        // Failing to start it is the system running out of threads, not bad input.
+        let pool = self
+            .threads
+            .map(|n| {
+                let pool = ThreadPoolBuilder::new().num_threads(n).build();
+                pool.map_err(|e| Error::Io(io::Error::other(format!("cannot start {} render threads: {}", n, e))))
+            })
+            .transpose()?;
+
+        let mut canvas = Canvas::new(self.width, self.height);
+        let width = self.width as usize;
+        progress.start(self.width as u64 * self.height as u64);
+
+        let mut shade_rows = || {
+            canvas.pixels_mut().par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    let (x, y) = (x as u32, y as u32);
+                    *pixel = self.sampling.pixel(x, y, |i, offset| {
+                        shader.shade(&self.sample_context(x, y, i, offset))
+                    });
+                }
+                progress.advance(width as u64);
+            })
+        };
+
+        match pool {
+            Some(pool) => pool.install(shade_rows),
+            None => shade_rows(),
+        }
+        progress.finish();
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::shader::UvGradient;
+
+    #[test]
+    fn thread_count_does_not_change_output() {
+        let shader = |ctx: &ShadeContext| {
+            Color::new((ctx.x * 0.37).sin(), (ctx.y * 1.3).cos() * ctx.u, ctx.v.sqrt())
+        };
+        let render = |threads| {
+            Renderer {
+                threads: Some(threads),
+                ..Renderer::new(97, 61)
+            }
+            .render(&shader)
+            .unwrap()
+        };
+
+        let single = render(1);
+        for &threads in &[2, 3, 8] {
+            assert!(render(threads) == single, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn closure_shaders_are_called_for_every_pixel() {
+        let renderer = Renderer {
+            time: 2.5,
+            ..Renderer::new(5, 3)
+        };
+        let shader = |ctx: &ShadeContext| Color::new(ctx.u, ctx.v, ctx.time * ctx.aspect_ratio());
+        let canvas = renderer.render(&shader).unwrap();
+        for y in 0..3 {
+            for x in 0..5 {
+                let expected = Color::new(x as f64 / 4.0, y as f64 / 2.0, 2.5 * 5.0 / 3.0);
+                assert_eq!(canvas.pixel(x, y), expected, "at ({}, {})", x, y);
+            }
+        }
+
+        // Trait objects render the same way, and a single row stays at v = 0.
+        let gradient: &dyn Shader = &UvGradient { blue: 0.5 };
+        let row = Renderer::new(3, 1).render(gradient).unwrap();
+        assert_eq!(row.pixels(), [Color::new(0.0, 0.0, 0.5), Color::new(0.5, 0.0, 0.5), Color::new(1.0, 0.0, 0.5)]);
+    }
+
+    #[test]
+    fn one_sample_is_taken_at_the_pixel_center() {
+        let shader = |ctx: &ShadeContext| Color::new(ctx.x, ctx.y, ctx.sample as f64);
+        let canvas = Renderer::new(5, 3).render(&shader).unwrap();
+        assert_eq!(canvas.pixel(4, 2), Color::new(4.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn samples_average_over_the_pixel() {
+        use crate::sampling::{Filter, Pattern};
+
+        // A vertical edge through the middle of pixel 0 should come out half gray.
+        let edge = |ctx: &ShadeContext| Color::gray(if ctx.x < 0.0 { 0.0 } else { 1.0 });
+        for pattern in [Pattern::Grid, Pattern::Jittered, Pattern::RotatedGrid, Pattern::Halton, Pattern::Sobol] {
+            let renderer = Renderer {
+                sampling: Sampling {
+                    samples: 256,
+                    pattern,
+                    filter: Filter::Box,
+                },
+                ..Renderer::new(1, 1)
+            };
+            let gray = renderer.render(&edge).unwrap().pixel(0, 0).r;
+            assert!((gray - 0.5).abs() < 0.05, "{:?} gave {}", pattern, gray);
+        }
+    }
+}
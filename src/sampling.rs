@@ -0,0 +1,380 @@
+//! Anti-aliasing: where a pixel is sampled and how the samples are combined.
+//!
+//! A [`Pattern`] places the samples in the unit square, which is stretched over the
+//! footprint of the reconstruction [`Filter`] centered on the pixel. Each sample is
+//! weighted by the filter, so wide filters also draw on the area of neighbouring
+//! pixels. Random patterns are seeded from the pixel position, so renders stay
+//! reproducible.
+
+use crate::color::Color;
+use crate::rng::{mix, Rng};
+use std::str::FromStr;
+
+/// How samples are spread over a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Pattern {
+    /// A regular grid. Counts are rounded to a square.
+    #[default]
+    Grid,
+    /// One random point in each cell of a grid. Counts are rounded to a square.
+    Jittered,
+    /// A grid turned by about 27 degrees, so that no two samples share a row or
+    /// column for small counts, as in RGSS. Counts are rounded to a square.
+    RotatedGrid,
+    /// The Halton sequence in bases 2 and 3, randomly shifted for each pixel.
+    Halton,
+    /// The first two dimensions of the Sobol sequence, randomly scrambled for each
+    /// pixel.
+    Sobol,
+}
+
+impl Pattern {
+    /// The number of samples actually taken when `requested` are asked for.
+    pub fn count(self, requested: u32) -> u32 {
+        match self {
+            Pattern::Grid | Pattern::Jittered | Pattern::RotatedGrid => grid_side(requested).pow(2),
+            Pattern::Halton | Pattern::Sobol => requested.max(1),
+        }
+    }
+
+    /// Calls `visit` with each of `count` sample positions in `0.0..1.0` for the
+    /// pixel identified by `seed`. Grid patterns round `count` as
+    /// [`count`](Pattern::count) does.
+    pub fn points(self, count: u32, seed: u64, mut visit: impl FnMut(u32, (f64, f64))) {
+        let side = grid_side(count);
+        let mut rng = Rng::new(seed);
+        match self {
+            Pattern::Grid | Pattern::Jittered | Pattern::RotatedGrid => {
+                for i in 0..side * side {
+                    let (col, row) = ((i % side) as f64, (i / side) as f64);
+                    let point = match self {
+                        Pattern::Jittered => (
+                            (col + rng.next_f64()) / side as f64,
+                            (row + rng.next_f64()) / side as f64,
+                        ),
+                        _ => ((col + 0.5) / side as f64, (row + 0.5) / side as f64),
+                    };
+                    let point = match self {
+                        Pattern::RotatedGrid => rotate(point),
+                        _ => point,
+                    };
+                    visit(i, point);
+                }
+            }
+            Pattern::Halton => {
+                let shift = (rng.next_f64(), rng.next_f64());
+                for i in 0..count {
+                    let x = radical_inverse(i as u64 + 1, 2) + shift.0;
+                    let y = radical_inverse(i as u64 + 1, 3) + shift.1;
+                    visit(i, (x.fract(), y.fract()));
+                }
+            }
+            Pattern::Sobol => {
+                let scramble = (rng.next_u64() as u32, rng.next_u64() as u32);
+                let unit = |v: u32| v as f64 / (1u64 << 32) as f64;
+                for i in 0..count {
+                    visit(i, (unit(i.reverse_bits() ^ scramble.0), unit(sobol2(i) ^ scramble.1)));
+                }
+            }
+        }
+    }
+}
+
+/// The side of the square grid closest to `requested` samples.
+fn grid_side(requested: u32) -> u32 {
+    (requested.max(1) as f64).sqrt().round() as u32
+}
+
+/// Turns a point of the unit square by `atan(1/2)` around its center, wrapping
+/// around the edges. A 2x2 grid becomes the classic rotated grid pattern.
+fn rotate((x, y): (f64, f64)) -> (f64, f64) {
+    let (sin, cos) = (0.5f64).atan().sin_cos();
+    let (dx, dy) = (x - 0.5, y - 0.5);
+    let wrap = |v: f64| (v + 0.5).rem_euclid(1.0);
+    (wrap(dx * cos - dy * sin), wrap(dx * sin + dy * cos))
+}
+
+/// The digits of `i` in `base`, mirrored around the radix point.
+fn radical_inverse(mut i: u64, base: u64) -> f64 {
+    let (mut result, mut scale) = (0.0, 1.0 / base as f64);
+    while i > 0 {
+        result += (i % base) as f64 * scale;
+        i /= base;
+        scale /= base as f64;
+    }
+    result
+}
+
+/// The second dimension of the Sobol sequence, as 32-bit fixed point.
+fn sobol2(mut i: u32) -> u32 {
+    let (mut v, mut result) = (1u32 << 31, 0);
+    while i != 0 {
+        if i & 1 == 1 {
+            result ^= v;
+        }
+        i >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+/// How much a sample counts toward a pixel, by its distance from the pixel center.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Filter {
+    /// Every sample within the pixel counts equally.
+    #[default]
+    Box,
+    /// Falls off linearly to zero one pixel away.
+    Tent,
+    /// A Gaussian with a standard deviation of half a pixel.
+    Gaussian,
+    /// Mitchell and Netravali's cubic with B = C = 1/3: sharp, with little ringing.
+    Mitchell,
+    /// A windowed sinc with two lobes. The sharpest, but it rings at hard edges.
+    Lanczos,
+}
+
+impl Filter {
+    /// How far from the pixel center the filter reaches, in pixels.
+    pub fn radius(self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell | Filter::Lanczos => 2.0,
+        }
+    }
+
+    /// The one-dimensional weight at distance `x`. Two-dimensional weights are the
+    /// product of the weights along each axis.
+    pub fn weight(self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - x,
+            // Shifted down so that it reaches zero at the edge.
+            Filter::Gaussian => {
+                let gaussian = |x: f64| (-2.0 * x * x).exp();
+                gaussian(x) - gaussian(self.radius())
+            }
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let value = if x < 1.0 {
+                    (12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)
+                } else {
+                    (-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                };
+                value / 6.0
+            }
+            Filter::Lanczos => {
+                let sinc = |x: f64| {
+                    if x < 1e-9 {
+                        1.0
+                    } else {
+                        let px = std::f64::consts::PI * x;
+                        px.sin() / px
+                    }
+                };
+                sinc(x) * sinc(x / self.radius())
+            }
+        }
+    }
+}
+
+/// Samples per pixel, their pattern and the filter that combines them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    pub samples: u32,
+    pub pattern: Pattern,
+    pub filter: Filter,
+}
+
+impl Default for Sampling {
+    /// One sample at the center of each pixel.
+    fn default() -> Self {
+        Sampling {
+            samples: 1,
+            pattern: Pattern::Grid,
+            filter: Filter::Box,
+        }
+    }
+}
+
+impl Sampling {
+    /// The filtered color of pixel `(x, y)`. `shade` is called with the index and
+    /// the offset from the pixel center of each sample.
+    pub fn pixel(&self, x: u32, y: u32, mut shade: impl FnMut(u32, (f64, f64)) -> Color) -> Color {
+        let count = self.pattern.count(self.samples);
+        let radius = self.filter.radius();
+        let (mut sum, mut weights, mut plain) = (Color::BLACK, 0.0, Color::BLACK);
+        self.pattern.points(count, mix((y as u64) << 32 | x as u64), |i, (sx, sy)| {
+            let offset = ((sx * 2.0 - 1.0) * radius, (sy * 2.0 - 1.0) * radius);
+            let weight = self.filter.weight(offset.0) * self.filter.weight(offset.1);
+            let color = shade(i, offset);
+            sum += color * weight;
+            weights += weight;
+            plain += color;
+        });
+        // Filters with negative lobes can cancel out with few samples.
+        if weights.abs() > 1e-6 {
+            sum / weights
+        } else {
+            plain / count as f64
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(Pattern::Grid),
+            "jittered" => Ok(Pattern::Jittered),
+            "rotated-grid" => Ok(Pattern::RotatedGrid),
+            "halton" => Ok(Pattern::Halton),
+            "sobol" => Ok(Pattern::Sobol),
+            _ => Err(format!(
+                "unknown sample pattern '{}' (expected grid, jittered, rotated-grid, halton or sobol)",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(Filter::Box),
+            "tent" => Ok(Filter::Tent),
+            "gaussian" => Ok(Filter::Gaussian),
+            "mitchell" => Ok(Filter::Mitchell),
+            "lanczos" => Ok(Filter::Lanczos),
+            _ => Err(format!(
+                "unknown filter '{}' (expected box, tent, gaussian, mitchell or lanczos)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(pattern: Pattern, count: u32, seed: u64) -> Vec<(f64, f64)> {
+        let mut points = Vec::new();
+        pattern.points(count, seed, |i, p| {
+            assert_eq!(i as usize, points.len());
+            points.push(p);
+        });
+        points
+    }
+
+    fn in_unit_square(points: &[(f64, f64)]) -> bool {
+        points.iter().all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y))
+    }
+
+    /// Whether each cell of a `columns` by `rows` grid holds exactly one point.
+    fn stratified(points: &[(f64, f64)], columns: usize, rows: usize) -> bool {
+        let mut counts = vec![0; columns * rows];
+        for &(x, y) in points {
+            // Nudged so that points on a cell boundary round-trip into the same cell.
+            let cell = |v: f64, n: usize| ((v * n as f64 + 1e-9) as usize).min(n - 1);
+            counts[cell(x, columns) + cell(y, rows) * columns] += 1;
+        }
+        counts.iter().all(|&n| n == 1)
+    }
+
+    #[test]
+    fn filters_peak_at_the_center_and_vanish_at_their_radius() {
+        let peaks = [
+            (Filter::Box, 1.0),
+            (Filter::Tent, 1.0),
+            (Filter::Gaussian, 1.0 - (-4.5f64).exp()),
+            (Filter::Mitchell, 8.0 / 9.0),
+            (Filter::Lanczos, 1.0),
+        ];
+        for &(filter, peak) in &peaks {
+            let radius = filter.radius();
+            assert!((filter.weight(0.0) - peak).abs() < 1e-12, "{:?}", filter);
+            assert_eq!(filter.weight(radius + 1e-9), 0.0, "{:?}", filter);
+            assert_eq!(filter.weight(-0.3), filter.weight(0.3), "{:?}", filter);
+            if filter != Filter::Box {
+                assert!(filter.weight(radius).abs() < 1e-12, "{:?}", filter);
+            }
+        }
+        // The box filter covers the pixel up to and including its edge.
+        assert_eq!(Filter::Box.weight(0.5), 1.0);
+        assert_eq!(Filter::Tent.weight(0.25), 0.75);
+        // Mitchell and Lanczos dip below zero in their outer lobe.
+        assert!(Filter::Mitchell.weight(1.5) < 0.0);
+        assert!(Filter::Lanczos.weight(1.5) < 0.0);
+    }
+
+    #[test]
+    fn grid_counts_round_to_the_nearest_square() {
+        let expected = [(0, 1), (1, 1), (2, 1), (3, 4), (4, 4), (6, 4), (7, 9), (12, 9), (13, 16), (100, 100)];
+        for &pattern in &[Pattern::Grid, Pattern::Jittered, Pattern::RotatedGrid] {
+            for &(requested, count) in &expected {
+                assert_eq!(pattern.count(requested), count, "{:?} {}", pattern, requested);
+                // points agrees, whether or not the count was rounded first.
+                assert_eq!(points(pattern, requested, 1).len(), count as usize, "{:?} {}", pattern, requested);
+            }
+        }
+        assert_eq!(Pattern::Halton.count(7), 7);
+        assert_eq!(Pattern::Sobol.count(0), 1);
+    }
+
+    #[test]
+    fn grid_patterns_put_one_sample_in_each_cell() {
+        let grid = points(Pattern::Grid, 9, 1);
+        assert_eq!(grid[0], (1.0 / 6.0, 1.0 / 6.0));
+        assert!(stratified(&grid, 3, 3));
+        for seed in 0..10 {
+            let jittered = points(Pattern::Jittered, 16, seed);
+            assert!(in_unit_square(&jittered) && stratified(&jittered, 4, 4));
+        }
+        // No two samples of the rotated 2x2 grid share a row or a column.
+        let rotated = points(Pattern::RotatedGrid, 4, 1);
+        assert!(in_unit_square(&rotated) && stratified(&rotated, 4, 1) && stratified(&rotated, 1, 4));
+    }
+
+    #[test]
+    fn halton_points_are_stratified_up_to_their_shift() {
+        for seed in 0..10 {
+            let shifted = points(Pattern::Halton, 36, seed);
+            assert!(in_unit_square(&shifted));
+            // Undo the per-pixel shift, which the first point reveals.
+            let raw = (radical_inverse(1, 2), radical_inverse(1, 3));
+            let shift = (shifted[0].0 - raw.0, shifted[0].1 - raw.1);
+            let unshifted: Vec<(f64, f64)> = shifted
+                .iter()
+                .map(|&(x, y)| ((x - shift.0).rem_euclid(1.0), (y - shift.1).rem_euclid(1.0)))
+                .collect();
+            assert!(stratified(&unshifted, 4, 9), "seed {}", seed);
+            assert!(stratified(&unshifted[..6], 2, 3), "seed {}", seed);
+        }
+        assert_ne!(points(Pattern::Halton, 4, 1), points(Pattern::Halton, 4, 2));
+    }
+
+    #[test]
+    fn sobol_points_fill_every_elementary_interval() {
+        // Scrambling by XOR keeps the first 2^m points a (0, m, 2)-net: every
+        // dyadic box of area 2^-m holds exactly one of them.
+        for seed in 0..10 {
+            let sobol = points(Pattern::Sobol, 16, seed);
+            assert!(in_unit_square(&sobol));
+            for k in 0..=4 {
+                assert!(stratified(&sobol, 1 << k, 1 << (4 - k)), "seed {} at 2^{}", seed, k);
+            }
+        }
+        assert_ne!(points(Pattern::Sobol, 4, 1), points(Pattern::Sobol, 4, 2));
+    }
+}
@@ -0,0 +1,298 @@
+//! Built-in scenes of objects in front of the sky.
+
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::hittable::{Hittable, HittableList};
+use crate::integrator::PathTracer;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal, Surface};
+use crate::mesh::Mesh;
+use crate::rng::{mix, Rng};
+use crate::shader::{ShadeContext, Shader};
+use crate::shapes::{Cone, Cuboid, Cylinder, Disk, Plane, Quad, Sphere, Triangle};
+use crate::sky::Sky;
+use crate::vec3::{Point3, Vec3};
+use crate::volume::Volume;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A collection of objects and where to look at them from.
+pub struct Scene {
+    pub world: HittableList,
+    pub position: Point3,
+    pub look_at: Point3,
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    /// The light from everywhere the objects leave open.
+    pub sky: Sky,
+}
+
+impl Scene {
+    /// The names accepted by [`builtin`](Scene::builtin).
+    pub const NAMES: &'static [&'static str] = &["book", "materials", "spheres", "shapes", "cornell"];
+
+    pub fn builtin(name: &str) -> Option<Scene> {
+        match name {
+            "book" => Some(Scene::book()),
+            "materials" => Some(Scene::materials()),
+            "spheres" => Some(Scene::spheres()),
+            "shapes" => Some(Scene::shapes()),
+            "cornell" => Some(Scene::cornell()),
+            _ => None,
+        }
+    }
+
+    /// The book's first scene: a sphere resting on a much larger one.
+    pub fn book() -> Scene {
+        let mut world = HittableList::new();
+        world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5));
+        world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0));
+        Scene {
+            world,
+            position: Point3::ZERO,
+            look_at: Point3::new(0.0, 0.0, -1.0),
+            fov: 90.0,
+            sky: Sky::default(),
+        }
+    }
+
+    /// The book's materials: a diffuse sphere between a hollow glass one and a
+    /// metal one.
+    pub fn materials() -> Scene {
+        let glass = Arc::new(Dielectric::new(1.5));
+        let mut world = HittableList::new();
+        world.add(Surface::new(
+            Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0),
+            Lambertian::new(Color::new(0.8, 0.8, 0.0)),
+        ));
+        world.add(Surface::new(
+            Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5),
+            Lambertian::new(Color::new(0.1, 0.2, 0.5)),
+        ));
+        world.add(Surface::shared(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5), glass.clone()));
+        // A negative radius turns the normals inward, leaving a bubble of air.
+        world.add(Surface::shared(Sphere::new(Point3::new(-1.0, 0.0, -1.0), -0.4), glass));
+        world.add(Surface::new(
+            Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5),
+            Metal::new(Color::new(0.8, 0.6, 0.2), 0.0),
+        ));
+        Scene {
+            world,
+            position: Point3::new(-2.0, 2.0, 1.0),
+            look_at: Point3::new(0.0, 0.0, -1.0),
+            fov: 30.0,
+            sky: Sky::default(),
+        }
+    }
+
+    /// The cover of the book: hundreds of small random spheres around three large
+    /// ones. The layout is the same on every run.
+    pub fn spheres() -> Scene {
+        let mut rng = Rng::new(0);
+        let mut random = |low: f64, high: f64| low + (high - low) * rng.next_f64();
+        let mut world = HittableList::new();
+        world.add(Surface::new(
+            Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0),
+            Lambertian::new(Color::gray(0.5)),
+        ));
+        for a in -11..11 {
+            for b in -11..11 {
+                let choice = random(0.0, 1.0);
+                let center = Point3::new(a as f64 + 0.9 * random(0.0, 1.0), 0.2, b as f64 + 0.9 * random(0.0, 1.0));
+                if (center - Point3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                    continue;
+                }
+                let sphere = Sphere::new(center, 0.2);
+                if choice < 0.8 {
+                    let albedo = Color::new(random(0.0, 1.0), random(0.0, 1.0), random(0.0, 1.0));
+                    let albedo = albedo * Color::new(random(0.0, 1.0), random(0.0, 1.0), random(0.0, 1.0));
+                    world.add(Surface::new(sphere, Lambertian::new(albedo)));
+                } else if choice < 0.95 {
+                    let albedo = Color::new(random(0.5, 1.0), random(0.5, 1.0), random(0.5, 1.0));
+                    world.add(Surface::new(sphere, Metal::new(albedo, random(0.0, 0.5))));
+                } else {
+                    world.add(Surface::new(sphere, Dielectric::new(1.5)));
+                }
+            }
+        }
+        world.add(Surface::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0), Dielectric::new(1.5)));
+        world.add(Surface::new(
+            Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0),
+            Lambertian::new(Color::new(0.4, 0.2, 0.1)),
+        ));
+        world.add(Surface::new(
+            Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0),
+            Metal::new(Color::new(0.7, 0.6, 0.5), 0.0),
+        ));
+        Scene {
+            world,
+            position: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::ZERO,
+            fov: 20.0,
+            sky: Sky::default(),
+        }
+    }
+
+    /// One of each primitive on a ground plane.
+    pub fn shapes() -> Scene {
+        let mut world = HittableList::new();
+        world.add(Surface::new(
+            Plane::new(Point3::ZERO, Vec3::new(0.0, 1.0, 0.0)),
+            Lambertian::new(Color::gray(0.5)),
+        ));
+        world.add(Surface::new(Sphere::new(Point3::new(-3.0, 0.6, 0.0), 0.6), Dielectric::new(1.5)));
+        world.add(Surface::new(
+            Cuboid::new(Point3::new(-1.9, 0.0, -0.5), Point3::new(-0.9, 1.0, 0.5)),
+            Lambertian::new(Color::new(0.7, 0.2, 0.15)),
+        ));
+        world.add(Surface::new(
+            Cylinder::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.2, 0.0), 0.5),
+            Metal::new(Color::new(0.9, 0.75, 0.4), 0.1),
+        ));
+        world.add(Surface::new(
+            Cone::new(Point3::new(1.4, 0.0, 0.0), Vec3::new(0.0, 1.4, 0.0), 0.6),
+            Lambertian::new(Color::new(0.2, 0.45, 0.2)),
+        ));
+        world.add(Surface::new(
+            Disk::new(Point3::new(3.0, 0.7, 0.0), Vec3::new(0.0, 0.5, 1.0), 0.6),
+            DiffuseLight::new(Color::gray(4.0)),
+        ));
+        world.add(Surface::new(
+            Quad::new(Point3::new(-2.2, 0.0, -2.0), Vec3::new(1.6, 0.0, 0.0), Vec3::new(0.0, 1.6, 0.0)),
+            Metal::new(Color::gray(0.9), 0.0),
+        ));
+        world.add(Surface::new(
+            Triangle::new(Point3::new(0.6, 0.0, -2.0), Point3::new(2.6, 0.0, -2.0), Point3::new(1.6, 1.7, -2.0)),
+            Lambertian::new(Color::new(0.2, 0.3, 0.7)),
+        ));
+        Scene {
+            world,
+            position: Point3::new(0.0, 2.5, 7.0),
+            look_at: Point3::new(0.0, 0.6, 0.0),
+            fov: 40.0,
+            sky: Sky::default(),
+        }
+    }
+
+    /// A mesh standing on a gray floor, seen from the front, above and to the
+    /// right, far enough away to fit in view.
+    pub fn mesh(mesh: Mesh) -> Scene {
+        let bounds = mesh.bounds().expect("meshes are loaded with at least one face");
+        let (center, radius) = (bounds.centroid(), bounds.size().length() / 2.0);
+        let fov = 40.0_f64;
+        let distance = 1.2 * radius / (fov.to_radians() / 2.0).sin();
+
+        let mut world = HittableList::new();
+        world.add(Surface::new(
+            Plane::new(Point3::new(0.0, bounds.min.y, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            Lambertian::new(Color::gray(0.5)),
+        ));
+        world.objects.extend(mesh.into_objects());
+        Scene {
+            world,
+            position: center + Vec3::new(0.5, 0.4, 1.0).normalize() * distance,
+            look_at: center,
+            fov,
+            sky: Sky::default(),
+        }
+    }
+
+    /// The Cornell box, lit only by the panel in its ceiling, with a block of
+    /// smoke and a block of fog in place of the usual boxes.
+    pub fn cornell() -> Scene {
+        let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+        let white: Arc<dyn Material> = Arc::new(Lambertian::new(Color::gray(0.73)));
+        let green: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+        let wall = |origin: [f64; 3], u: [f64; 3], v: [f64; 3], material: &Arc<dyn Material>| {
+            Surface::shared(Quad::new(origin.into(), u.into(), v.into()), material.clone())
+        };
+
+        let mut world = HittableList::new();
+        world.add(wall([555.0, 0.0, 0.0], [0.0, 555.0, 0.0], [0.0, 0.0, 555.0], &green));
+        world.add(wall([0.0, 0.0, 0.0], [0.0, 555.0, 0.0], [0.0, 0.0, 555.0], &red));
+        world.add(wall([0.0, 0.0, 0.0], [555.0, 0.0, 0.0], [0.0, 0.0, 555.0], &white));
+        world.add(wall([555.0, 555.0, 555.0], [-555.0, 0.0, 0.0], [0.0, 0.0, -555.0], &white));
+        world.add(wall([0.0, 0.0, 555.0], [555.0, 0.0, 0.0], [0.0, 555.0, 0.0], &white));
+        // Facing down, so that it lights the room and not the roof.
+        world.add(Surface::new(
+            Quad::new(Point3::new(213.0, 554.0, 227.0), Vec3::new(130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 105.0)),
+            DiffuseLight::new(Color::gray(15.0)),
+        ));
+        world.add(Volume::new(
+            Cuboid::new(Point3::new(265.0, 0.0, 295.0), Point3::new(430.0, 330.0, 460.0)),
+            0.01,
+            Color::BLACK,
+        ));
+        world.add(Volume::new(
+            Cuboid::new(Point3::new(130.0, 0.0, 65.0), Point3::new(295.0, 165.0, 230.0)),
+            0.01,
+            Color::WHITE,
+        ));
+        Scene {
+            world,
+            position: Point3::new(278.0, 278.0, -800.0),
+            look_at: Point3::new(278.0, 278.0, 0.0),
+            fov: 40.0,
+            sky: Sky {
+                horizon: Color::BLACK,
+                zenith: Color::BLACK,
+            },
+        }
+    }
+}
+
+/// What a surface is colored by.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Shading {
+    /// The light reaching the camera, path traced through the materials.
+    #[default]
+    Path,
+    /// The surface normal, with each axis mapped from `-1..1` to `0..1`.
+    Normal,
+    /// The surface coordinates as red and green, repeating every unit.
+    Uv,
+}
+
+/// Renders the nearest surface along each camera ray, or the sky behind them.
+pub struct SceneShader {
+    pub camera: Camera,
+    pub sky: Sky,
+    pub world: Bvh,
+    pub shading: Shading,
+    pub tracer: PathTracer,
+}
+
+impl Shader for SceneShader {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        let ray = self.camera.ray_for(ctx);
+        if self.shading == Shading::Path {
+            // Every sample of every pixel follows its own reproducible paths.
+            let seed = mix(ctx.x.to_bits() ^ mix(ctx.y.to_bits() ^ mix(ctx.sample as u64)));
+            return self.tracer.radiance(&self.world, &self.sky, ray, &mut Rng::new(seed));
+        }
+        match self.world.hit(&ray, 1e-3, f64::INFINITY) {
+            Some(hit) if self.shading == Shading::Uv => {
+                Color::new(hit.uv.0.rem_euclid(1.0), hit.uv.1.rem_euclid(1.0), 0.0)
+            }
+            Some(hit) => Color::from((hit.normal + Vec3::ONE) * 0.5),
+            None => self.sky.color(&ray),
+        }
+    }
+
+    fn stats(&self) -> Option<String> {
+        Some(self.world.stats().to_string())
+    }
+}
+
+impl FromStr for Shading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(Shading::Path),
+            "normal" => Ok(Shading::Normal),
+            "uv" => Ok(Shading::Uv),
+            _ => Err(format!("unknown shading '{}' (expected path, normal or uv)", s)),
+        }
+    }
+}
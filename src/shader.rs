@@ -0,0 +1,84 @@
+//! Per-pixel color functions.
+
+use crate::color::Color;
+
+/// Where and when a shader is being evaluated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadeContext {
+    /// Position in pixels, with `(0, 0)` at the center of the top left pixel.
+    pub x: f64,
+    pub y: f64,
+    /// Position normalized so that the centers of the first and last pixel in each
+    /// direction are at 0 and 1. `v` grows downwards, like `y`.
+    pub u: f64,
+    pub v: f64,
+    /// Resolution of the image being rendered.
+    pub width: u32,
+    pub height: u32,
+    /// Animation time in seconds.
+    pub time: f64,
+    /// Index of the sample within the pixel, for shaders that vary per sample.
+    pub sample: u32,
+}
+
+impl ShadeContext {
+    /// The context for pixel position `(x, y)` in a `width` x `height` image.
+    pub fn new(x: f64, y: f64, width: u32, height: u32) -> ShadeContext {
+        // A single row or column would divide by zero, so pin it to the start.
+        ShadeContext {
+            x,
+            y,
+            u: x / (width.max(2) - 1) as f64,
+            v: y / (height.max(2) - 1) as f64,
+            width,
+            height,
+            time: 0.0,
+            sample: 0,
+        }
+    }
+
+    /// Width divided by height.
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+/// Computes a linear color for any point of the image.
+///
+/// Shaders are called from several threads at once and must not depend on the
+/// order pixels are visited in.
+pub trait Shader: Sync {
+    fn shade(&self, ctx: &ShadeContext) -> Color;
+
+    /// A summary of work done while rendering, for shaders that keep count.
+    fn stats(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<F> Shader for F
+where
+    F: Fn(&ShadeContext) -> Color + Sync,
+{
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        self(ctx)
+    }
+}
+
+/// The original image: red grows along x, green along y, and blue is constant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvGradient {
+    pub blue: f64,
+}
+
+impl Default for UvGradient {
+    fn default() -> Self {
+        UvGradient { blue: 0.25 }
+    }
+}
+
+impl Shader for UvGradient {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        Color::new(ctx.u, ctx.v, self.blue)
+    }
+}
@@ -0,0 +1,531 @@
+//! Geometric primitives.
+//!
+//! Every shape reports an outward normal and surface coordinates with its hits.
+//! Shapes with a free orientation take it from their vectors; the rest are aligned
+//! with the axes.
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use std::f64::consts::{PI, TAU};
+
+/// Two unit vectors that form a right-handed basis with the unit vector `n`.
+fn basis(n: Vec3) -> (Vec3, Vec3) {
+    // Any axis that is not too close to `n` will do.
+    let helper = if n.x.abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let e1 = helper.cross(n).normalize();
+    (e1, n.cross(e1))
+}
+
+/// The roots of `a t² + 2 half_b t + c` in increasing order.
+fn roots(a: f64, half_b: f64, c: f64) -> Option<[f64; 2]> {
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 || a == 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    let (t0, t1) = ((-half_b - sqrt) / a, (-half_b + sqrt) / a);
+    Some(if t0 <= t1 { [t0, t1] } else { [t1, t0] })
+}
+
+/// The angle of `v` around an axis with the basis `(e1, e2)`, as a fraction of a
+/// turn in `0.0..1.0`.
+fn turn(v: Vec3, (e1, e2): (Vec3, Vec3)) -> f64 {
+    (v.dot(e2).atan2(v.dot(e1)) / TAU).rem_euclid(1.0)
+}
+
+/// The box around a disk with unit normal `n`.
+fn disk_bounds(center: Point3, n: Vec3, radius: f64) -> Aabb {
+    let extent = |c: f64| radius * (1.0 - c * c).max(0.0).sqrt();
+    let half = Vec3::new(extent(n.x), extent(n.y), extent(n.z));
+    Aabb::new(center - half, center + half)
+}
+
+/// A hit on a temporary shape, such as the cap of a cylinder, freed from it.
+/// Shapes carry no material, so nothing is lost.
+fn detach<'a>(hit: HitRecord<'_>) -> HitRecord<'a> {
+    HitRecord {
+        t: hit.t,
+        point: hit.point,
+        normal: hit.normal,
+        front_face: hit.front_face,
+        uv: hit.uv,
+        material: None,
+    }
+}
+
+/// Keeps the nearer of two optional hits.
+fn nearer<'a>(a: Option<HitRecord<'a>>, b: Option<HitRecord<'a>>) -> Option<HitRecord<'a>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.t < a.t { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Point3,
+    pub radius: f64,
+}
+
+impl Sphere {
+    pub fn new(center: Point3, radius: f64) -> Sphere {
+        Sphere { center, radius }
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let oc = ray.origin - self.center;
+        let [near, far] = roots(
+            ray.direction.length_squared(),
+            oc.dot(ray.direction),
+            oc.length_squared() - self.radius * self.radius,
+        )?;
+        let t = [near, far].iter().copied().find(|&t| t > t_min && t < t_max)?;
+        let normal = (ray.at(t) - self.center) / self.radius;
+        // Longitude from -x around through +z, latitude from the south pole.
+        let u = (normal.z.atan2(-normal.x) + PI) / TAU;
+        let v = (-normal.y).clamp(-1.0, 1.0).acos() / PI;
+        Some(HitRecord::new(ray, t, normal, (u, v)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let half = Vec3::splat(self.radius.abs());
+        Some(Aabb::new(self.center - half, self.center + half))
+    }
+}
+
+/// An infinite plane. Its surface coordinates are distances from `point` along two
+/// directions in the plane, so textures repeat rather than stretch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub point: Point3,
+    /// Unit normal.
+    pub normal: Vec3,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3) -> Plane {
+        Plane {
+            point,
+            normal: normal.normalize(),
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(self.point - ray.origin) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let (e1, e2) = basis(self.normal);
+        let local = ray.at(t) - self.point;
+        Some(HitRecord::new(ray, t, self.normal, (local.dot(e1), local.dot(e2))))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+/// An axis-aligned box between two opposite corners.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cuboid {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Cuboid {
+    /// The box with corners `a` and `b`, in any order.
+    pub fn new(a: Point3, b: Point3) -> Cuboid {
+        Cuboid {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // The slab method: intersect the ranges of t inside each pair of faces,
+        // remembering which axis each end of the range came from.
+        let (mut near, mut far) = ((f64::NEG_INFINITY, 0), (f64::INFINITY, 0));
+        for axis in 0..3 {
+            let (origin, direction) = (ray.origin[axis], ray.direction[axis]);
+            let (low, high) = (self.min[axis], self.max[axis]);
+            if direction == 0.0 {
+                if origin < low || origin > high {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((low - origin) / direction, (high - origin) / direction);
+            let (t0, t1) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > near.0 {
+                near = (t0, axis);
+            }
+            if t1 < far.0 {
+                far = (t1, axis);
+            }
+        }
+        if near.0 > far.0 {
+            return None;
+        }
+
+        // From inside the box, the ray leaves through the far face.
+        let (t, axis, entering) = if near.0 > t_min && near.0 < t_max {
+            (near.0, near.1, true)
+        } else if far.0 > t_min && far.0 < t_max {
+            (far.0, far.1, false)
+        } else {
+            return None;
+        };
+        let outward = if (ray.direction[axis] < 0.0) == entering { 1.0 } else { -1.0 };
+        let mut normal = [0.0; 3];
+        normal[axis] = outward;
+
+        // Face coordinates from the other two axes, in order.
+        let point = ray.at(t);
+        let local = |a: usize| (point[a] - self.min[a]) / (self.max[a] - self.min[a]);
+        let uv = ((local((axis + 1) % 3)), local((axis + 2) % 3));
+        Some(HitRecord::new(ray, t, Vec3::from(normal), uv))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+}
+
+/// A flat disk. `u` goes around it and `v` from the center to the rim.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disk {
+    pub center: Point3,
+    /// Unit normal.
+    pub normal: Vec3,
+    pub radius: f64,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64) -> Disk {
+        Disk {
+            center,
+            normal: normal.normalize(),
+            radius,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let plane = Plane::new(self.center, self.normal);
+        let hit = plane.hit(ray, t_min, t_max)?;
+        let local = hit.point - self.center;
+        let distance = local.length();
+        if distance > self.radius {
+            return None;
+        }
+        let uv = (turn(local, basis(self.normal)), distance / self.radius);
+        Some(HitRecord::new(ray, hit.t, self.normal, uv))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(disk_bounds(self.center, self.normal, self.radius))
+    }
+}
+
+/// A parallelogram with one corner at `corner` and sides `u` and `v`. The normal
+/// is `u × v`, and the surface coordinates run along the two sides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quad {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3) -> Quad {
+        Quad { corner, u, v }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let n = self.u.cross(self.v);
+        let normal = n.normalize();
+        let plane = Plane::new(self.corner, normal);
+        let hit = plane.hit(ray, t_min, t_max)?;
+
+        // The hit point in the coordinates of the two sides.
+        let w = n / n.length_squared();
+        let p = hit.point - self.corner;
+        let (alpha, beta) = (w.dot(p.cross(self.v)), w.dot(self.u.cross(p)));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some(HitRecord::new(ray, hit.t, normal, (alpha, beta)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let c = self.corner;
+        Aabb::around(&[c, c + self.u, c + self.v, c + self.u + self.v])
+    }
+}
+
+/// A triangle, with the normal on the side from which `a`, `b`, `c` run
+/// counter-clockwise. The surface coordinates are the barycentric weights of `b`
+/// and `c`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Point3,
+    pub b: Point3,
+    pub c: Point3,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3) -> Triangle {
+        Triangle { a, b, c }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // Möller and Trumbore's algorithm.
+        let (e1, e2) = (self.b - self.a, self.c - self.a);
+        let p = ray.direction.cross(e2);
+        let determinant = e1.dot(p);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let s = ray.origin - self.a;
+        let u = s.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = ray.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(q) * inverse;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        Some(HitRecord::new(ray, t, e1.cross(e2).normalize(), (u, v)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Aabb::around(&[self.a, self.b, self.c])
+    }
+}
+
+/// A closed cylinder from `base` to `base + axis`. On the side `u` goes around it
+/// and `v` along it; the caps are mapped like disks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cylinder {
+    pub base: Point3,
+    pub axis: Vec3,
+    pub radius: f64,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64) -> Cylinder {
+        Cylinder { base, axis, radius }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let height = self.axis.length();
+        let axis = self.axis / height;
+        let oc = ray.origin - self.base;
+
+        // The side is where the distance from the axis equals the radius.
+        let perpendicular = |v: Vec3| v - axis * v.dot(axis);
+        let (d, o) = (perpendicular(ray.direction), perpendicular(oc));
+        let side = roots(d.length_squared(), d.dot(o), o.length_squared() - self.radius * self.radius)
+            .and_then(|ts| {
+                ts.iter().copied().find_map(|t| {
+                    let along = (oc + ray.direction * t).dot(axis);
+                    if t <= t_min || t >= t_max || !(0.0..=height).contains(&along) {
+                        return None;
+                    }
+                    let radial = perpendicular(oc + ray.direction * t) / self.radius;
+                    let uv = (turn(radial, basis(axis)), along / height);
+                    Some(HitRecord::new(ray, t, radial, uv))
+                })
+            });
+
+        let bottom = Disk::new(self.base, -axis, self.radius).hit(ray, t_min, t_max).map(detach);
+        let top = Disk::new(self.base + self.axis, axis, self.radius).hit(ray, t_min, t_max).map(detach);
+        nearer(side, nearer(bottom, top))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let axis = self.axis.normalize();
+        let bottom = disk_bounds(self.base, axis, self.radius);
+        Some(bottom.union(disk_bounds(self.base + self.axis, axis, self.radius)))
+    }
+}
+
+/// A closed cone with its base disk at `base` and its apex at `base + axis`. On the
+/// side `u` goes around it and `v` from the base to the apex.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cone {
+    pub base: Point3,
+    pub axis: Vec3,
+    pub radius: f64,
+}
+
+impl Cone {
+    pub fn new(base: Point3, axis: Vec3, radius: f64) -> Cone {
+        Cone { base, axis, radius }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let height = self.axis.length();
+        let axis = self.axis / height;
+        let slope = self.radius / height;
+        let apex = self.base + self.axis;
+
+        // Measured from the apex, the side is where the distance from the axis is
+        // `slope` times the distance along it: |q|² = (1 + slope²)(q·axis)².
+        let oc = ray.origin - apex;
+        let k = 1.0 + slope * slope;
+        let (d_axis, o_axis) = (ray.direction.dot(axis), oc.dot(axis));
+        let a = ray.direction.length_squared() - k * d_axis * d_axis;
+        let half_b = ray.direction.dot(oc) - k * d_axis * o_axis;
+        let c = oc.length_squared() - k * o_axis * o_axis;
+        let candidates = if a.abs() < 1e-12 {
+            // Parallel to the slant, the quadratic degenerates to a line.
+            (half_b != 0.0).then(|| [-c / (2.0 * half_b); 2])
+        } else {
+            roots(a, half_b, c)
+        };
+
+        let side = candidates.and_then(|ts| {
+            ts.iter().copied().find_map(|t| {
+                let q = oc + ray.direction * t;
+                // Only the nappe below the apex, down to the base.
+                let along = -q.dot(axis);
+                if t <= t_min || t >= t_max || !(0.0..=height).contains(&along) {
+                    return None;
+                }
+                let radial = q - axis * q.dot(axis);
+                let outward = if radial.near_zero() {
+                    axis
+                } else {
+                    (radial.normalize() + axis * slope).normalize()
+                };
+                let uv = (turn(radial, basis(axis)), 1.0 - along / height);
+                Some(HitRecord::new(ray, t, outward, uv))
+            })
+        });
+        let bottom = Disk::new(self.base, -axis, self.radius).hit(ray, t_min, t_max).map(detach);
+        nearer(side, bottom)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(disk_bounds(self.base, self.axis.normalize(), self.radius).grow(self.base + self.axis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shoots a ray from `origin` towards `target` and returns the hit point and
+    /// outward normal.
+    fn shoot(shape: &dyn Hittable, origin: Point3, target: Point3) -> Option<(Point3, Vec3)> {
+        let ray = Ray::new(origin, target - origin);
+        shape.hit(&ray, 1e-9, f64::INFINITY).map(|hit| {
+            let outward = if hit.front_face { hit.normal } else { -hit.normal };
+            (hit.point, outward)
+        })
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn every_shape_is_hit_on_its_surface() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let eye = Point3::new(0.0, 5.0, 0.0);
+        let shapes: Vec<(Box<dyn Hittable>, Point3)> = vec![
+            (Box::new(Sphere::new(Point3::ZERO, 2.0)), Point3::new(0.0, 2.0, 0.0)),
+            (Box::new(Plane::new(Point3::ZERO, up)), Point3::ZERO),
+            (Box::new(Cuboid::new(Point3::splat(2.0), Point3::splat(-2.0))), Point3::new(0.0, 2.0, 0.0)),
+            (Box::new(Disk::new(Point3::ZERO, up, 1.0)), Point3::ZERO),
+            (
+                Box::new(Quad::new(Point3::new(-1.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 2.0), Vec3::new(2.0, 0.0, 0.0))),
+                Point3::ZERO,
+            ),
+            (
+                Box::new(Triangle::new(
+                    Point3::new(-1.0, 0.0, -1.0),
+                    Point3::new(-1.0, 0.0, 2.0),
+                    Point3::new(2.0, 0.0, -1.0),
+                )),
+                Point3::ZERO,
+            ),
+            (Box::new(Cylinder::new(Point3::ZERO, Vec3::new(0.0, 3.0, 0.0), 1.0)), Point3::new(0.0, 3.0, 0.0)),
+            (
+                Box::new(Cone::new(Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -3.0, 0.0), 1.0)),
+                Point3::new(0.0, 3.0, 0.0),
+            ),
+        ];
+        for (i, (shape, expected)) in shapes.iter().enumerate() {
+            let (point, normal) = shoot(&**shape, eye, Point3::ZERO).unwrap_or_else(|| panic!("shape {} missed", i));
+            assert_close(point, *expected);
+            assert_close(normal, up);
+        }
+    }
+
+    #[test]
+    fn normals_face_the_ray_from_inside() {
+        let sphere = Sphere::new(Point3::ZERO, 1.0);
+        let hit = sphere.hit(&Ray::new(Point3::ZERO, Vec3::new(1.0, 0.0, 0.0)), 1e-9, f64::INFINITY).unwrap();
+        assert!(!hit.front_face);
+        assert_close(hit.normal, Vec3::new(-1.0, 0.0, 0.0));
+
+        let cuboid = Cuboid::new(Point3::splat(-1.0), Point3::splat(1.0));
+        let hit = cuboid.hit(&Ray::new(Point3::ZERO, Vec3::new(0.0, 0.0, -2.0)), 1e-9, f64::INFINITY).unwrap();
+        assert!(!hit.front_face);
+        assert_eq!(hit.t, 0.5);
+        assert_close(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn side_hits_have_radial_normals() {
+        let side = Point3::new(5.0, 1.0, 0.0);
+        let cylinder = Cylinder::new(Point3::ZERO, Vec3::new(0.0, 2.0, 0.0), 1.0);
+        let (point, normal) = shoot(&cylinder, side, Point3::new(0.0, 1.0, 0.0)).unwrap();
+        assert_close(point, Point3::new(1.0, 1.0, 0.0));
+        assert_close(normal, Vec3::new(1.0, 0.0, 0.0));
+
+        // A cone as wide as it is tall has its sides at 45 degrees.
+        let cone = Cone::new(Point3::ZERO, Vec3::new(0.0, 2.0, 0.0), 2.0);
+        let (point, normal) = shoot(&cone, side, Point3::new(0.0, 1.0, 0.0)).unwrap();
+        assert_close(point, Point3::new(1.0, 1.0, 0.0));
+        assert_close(normal, Vec3::new(1.0, 1.0, 0.0).normalize());
+
+        // Rays past the ends or the rim miss.
+        assert!(shoot(&cylinder, Point3::new(5.0, 3.0, 0.0), Point3::new(0.0, 3.0, 0.0)).is_none());
+        let disk = Disk::new(Point3::ZERO, Vec3::new(0.0, 1.0, 0.0), 1.0);
+        assert!(shoot(&disk, Point3::new(1.5, 1.0, 0.0), Point3::new(1.5, 0.0, 0.0)).is_none());
+    }
+}
@@ -0,0 +1,44 @@
+//! A background that depends only on the direction of the view ray.
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::ray::Ray;
+use crate::shader::{ShadeContext, Shader};
+
+/// A vertical blend from `horizon` for rays pointing straight down to `zenith` for
+/// rays pointing straight up, as in the book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sky {
+    pub horizon: Color,
+    pub zenith: Color,
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Sky {
+            horizon: Color::WHITE,
+            zenith: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Sky {
+    /// The color seen along `ray`.
+    pub fn color(&self, ray: &Ray) -> Color {
+        let t = 0.5 * (ray.direction.normalize().y + 1.0);
+        self.horizon.lerp(self.zenith, t)
+    }
+}
+
+/// Renders the sky as seen through a camera.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SkyShader {
+    pub camera: Camera,
+    pub sky: Sky,
+}
+
+impl Shader for SkyShader {
+    fn shade(&self, ctx: &ShadeContext) -> Color {
+        self.sky.color(&self.camera.ray_for(ctx))
+    }
+}
@@ -0,0 +1,149 @@
+//! Transfer functions that encode linear light for display.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The opto-electronic transfer function applied to linear values before they are
+/// quantized, and recorded in output files that can describe it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Transfer {
+    /// Write values unchanged and leave the output untagged, so viewers treat them
+    /// as already display-encoded. This is how the book's images were made.
+    #[default]
+    PassThrough,
+    /// The exact piecewise sRGB curve from IEC 61966-2-1.
+    Srgb,
+    /// A pure power law `v^(1/gamma)`.
+    Gamma(f64),
+}
+
+impl Transfer {
+    /// The range of exponents accepted for [`Transfer::Gamma`]. Outside it the PNG
+    /// `gAMA` chunk and the profile's u8.8 tone curve cannot record the value.
+    pub const GAMMA_RANGE: std::ops::RangeInclusive<f64> = 0.1..=10.0;
+
+    /// Encodes a linear value in `0.0..=1.0`.
+    pub fn encode(self, v: f64) -> f64 {
+        match self {
+            Transfer::PassThrough => v,
+            Transfer::Srgb => {
+                if v <= 0.003_130_8 {
+                    12.92 * v
+                } else {
+                    1.055 * v.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Transfer::Gamma(gamma) => v.powf(1.0 / gamma),
+        }
+    }
+
+    /// The inverse of [`encode`](Transfer::encode), turning an encoded value back
+    /// into linear light.
+    pub fn decode(self, v: f64) -> f64 {
+        match self {
+            Transfer::PassThrough => v,
+            Transfer::Srgb => {
+                if v <= 0.040_45 {
+                    v / 12.92
+                } else {
+                    ((v + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            Transfer::Gamma(gamma) => v.powf(gamma),
+        }
+    }
+
+    /// The exponent a decoder should apply to get back to linear light, as recorded
+    /// in a PNG `gAMA` chunk. sRGB is approximated by 2.2 as the PNG spec requires.
+    /// Pass-through output makes no claim.
+    pub fn decoding_gamma(self) -> Option<f64> {
+        match self {
+            Transfer::PassThrough => None,
+            Transfer::Srgb => Some(2.2),
+            Transfer::Gamma(gamma) => Some(gamma),
+        }
+    }
+}
+
+impl fmt::Display for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transfer::PassThrough => f.write_str("none"),
+            Transfer::Srgb => f.write_str("srgb"),
+            Transfer::Gamma(gamma) => write!(f, "gamma:{}", gamma),
+        }
+    }
+}
+
+impl FromStr for Transfer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Transfer::PassThrough),
+            "linear" => Ok(Transfer::Gamma(1.0)),
+            "srgb" => Ok(Transfer::Srgb),
+            _ => match s.strip_prefix("gamma:").map(str::parse::<f64>) {
+                Some(Ok(gamma)) if Transfer::GAMMA_RANGE.contains(&gamma) => Ok(Transfer::Gamma(gamma)),
+                Some(Ok(gamma)) => Err(format!(
+                    "gamma {} is out of range (expected {} to {})",
+                    gamma,
+                    Transfer::GAMMA_RANGE.start(),
+                    Transfer::GAMMA_RANGE.end()
+                )),
+                _ => Err(format!("unknown transfer function '{}' (expected none, linear, srgb or gamma:<N>)", s)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_inverts_encode() {
+        for &transfer in &[Transfer::PassThrough, Transfer::Srgb, Transfer::Gamma(1.0), Transfer::Gamma(2.2)] {
+            for i in 0..=1000 {
+                let v = i as f64 / 1000.0;
+                assert!((transfer.decode(transfer.encode(v)) - v).abs() < 1e-12, "{} at {}", transfer, v);
+                assert!((transfer.encode(transfer.decode(v)) - v).abs() < 1e-12, "{} at {}", transfer, v);
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_segments_meet_at_the_linear_boundary() {
+        let srgb = Transfer::Srgb;
+        // The linear segment ends at 0.0031308, which encodes to 0.04045. The
+        // standard's rounded constants leave a step of a few 1e-8 there.
+        assert!((srgb.encode(0.003_130_8) - 0.040_45).abs() < 1e-6);
+        let below = srgb.encode(0.003_130_8 - 1e-12);
+        let above = srgb.encode(0.003_130_8 + 1e-12);
+        assert!((above - below).abs() < 1e-7);
+        assert!((srgb.decode(0.040_45) - 0.003_130_8).abs() < 1e-6);
+        assert!((srgb.decode(0.040_45 + 1e-9) - srgb.decode(0.040_45)).abs() < 1e-6);
+        // Middle gray and the ends of the range.
+        assert!((srgb.encode(0.5) - 0.735_356_6).abs() < 1e-6);
+        assert_eq!(srgb.encode(0.0), 0.0);
+        assert!((srgb.encode(1.0) - 1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn parses_and_prints_curves() {
+        for s in &["none", "srgb", "gamma:2.2"] {
+            assert_eq!(s.parse::<Transfer>().unwrap().to_string(), *s);
+        }
+        assert_eq!("linear".parse(), Ok(Transfer::Gamma(1.0)));
+        assert!("gamma:x".parse::<Transfer>().is_err());
+    }
+
+    #[test]
+    fn rejects_gammas_out_of_range() {
+        assert_eq!("gamma:0.1".parse(), Ok(Transfer::Gamma(0.1)));
+        assert_eq!("gamma:10".parse(), Ok(Transfer::Gamma(10.0)));
+        for s in &["gamma:0", "gamma:-2", "gamma:0.09", "gamma:10.5", "gamma:256", "gamma:1e300", "gamma:inf", "gamma:NaN"] {
+            assert!(s.parse::<Transfer>().unwrap_err().contains("out of range"), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Three-dimensional vectors and points.
+
+use crate::color::Color;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// A position, as opposed to a direction. The same type, named for readability.
+pub type Point3 = Vec3;
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    pub const ONE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    /// A vector with all three components set to `v`.
+    pub const fn splat(v: f64) -> Vec3 {
+        Vec3::new(v, v, v)
+    }
+
+    pub fn dot(self, other: Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// The right-handed cross product.
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    /// The vector scaled to length 1. The zero vector has no direction and gives
+    /// NaNs.
+    pub fn normalize(self) -> Vec3 {
+        self / self.length()
+    }
+
+    /// Whether every component is so small that the vector has no usable direction.
+    pub fn near_zero(self) -> bool {
+        const EPSILON: f64 = 1e-8;
+        self.x.abs() < EPSILON && self.y.abs() < EPSILON && self.z.abs() < EPSILON
+    }
+
+    /// Mirrors the vector about the surface with unit normal `n`.
+    pub fn reflect(self, n: Vec3) -> Vec3 {
+        self - n * (2.0 * self.dot(n))
+    }
+
+    /// Bends the unit vector at a surface with unit normal `n` by Snell's law, where
+    /// `eta_ratio` is the refractive index on the incoming side divided by the one
+    /// on the far side. Returns `None` on total internal reflection.
+    pub fn refract(self, n: Vec3, eta_ratio: f64) -> Option<Vec3> {
+        let cos_theta = (-self).dot(n).min(1.0);
+        let sin_theta_squared = 1.0 - cos_theta * cos_theta;
+        if eta_ratio * eta_ratio * sin_theta_squared > 1.0 {
+            return None;
+        }
+        let perpendicular = (self + n * cos_theta) * eta_ratio;
+        let parallel = n * -(1.0 - perpendicular.length_squared()).abs().sqrt();
+        Some(perpendicular + parallel)
+    }
+
+    /// Linear interpolation: `self` at `t = 0` and `other` at `t = 1`.
+    pub fn lerp(self, other: Vec3, t: f64) -> Vec3 {
+        self + (other - self) * t
+    }
+
+    /// The smaller of each pair of components.
+    pub fn min(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+
+    /// The larger of each pair of components.
+    pub fn max(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+
+    pub fn min_element(self) -> f64 {
+        self.x.min(self.y).min(self.z)
+    }
+
+    pub fn max_element(self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Component-wise product.
+impl Mul for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, t: f64) -> Vec3 {
+        Vec3::new(self.x * t, self.y * t, self.z * t)
+    }
+}
+
+impl Mul<Vec3> for f64 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        v * self
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, t: f64) -> Vec3 {
+        self * (1.0 / t)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, other: Vec3) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, other: Vec3) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f64> for Vec3 {
+    fn mul_assign(&mut self, t: f64) {
+        *self = *self * t;
+    }
+}
+
+impl DivAssign<f64> for Vec3 {
+    fn div_assign(&mut self, t: f64) {
+        *self = *self / t;
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("axis {} out of range for Vec3", axis),
+        }
+    }
+}
+
+impl From<[f64; 3]> for Vec3 {
+    fn from([x, y, z]: [f64; 3]) -> Vec3 {
+        Vec3::new(x, y, z)
+    }
+}
+
+impl From<Color> for Vec3 {
+    fn from(c: Color) -> Vec3 {
+        Vec3::new(c.r, c.g, c.b)
+    }
+}
+
+impl From<Vec3> for Color {
+    fn from(v: Vec3) -> Color {
+        Color::new(v.x, v.y, v.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn arithmetic_is_component_wise() {
+        let (a, b) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, -5.0, 6.0));
+        assert_eq!(a + b, Vec3::new(5.0, -3.0, 9.0));
+        assert_eq!(a - b, Vec3::new(-3.0, 7.0, -3.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(a * b, Vec3::new(4.0, -10.0, 18.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(b / 2.0, Vec3::new(2.0, -2.5, 3.0));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+        c *= 3.0;
+        c /= 3.0;
+        assert_eq!(c, b);
+        assert_eq!((c[0], c[1], c[2]), (4.0, -5.0, 6.0));
+    }
+
+    #[test]
+    fn dot_cross_and_length() {
+        let (x, y, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(x.cross(y), z);
+        assert_eq!(y.cross(z), x);
+        assert_eq!(z.cross(x), y);
+        assert_eq!(y.cross(x), -z);
+
+        let v = Vec3::new(2.0, -3.0, 6.0);
+        assert_eq!(v.dot(Vec3::new(1.0, 1.0, 1.0)), 5.0);
+        assert_eq!(v.length_squared(), 49.0);
+        assert_eq!(v.length(), 7.0);
+        assert_close(v.normalize(), Vec3::new(2.0, -3.0, 6.0) / 7.0);
+        assert_eq!(v.cross(v), Vec3::ZERO);
+        assert!(Vec3::splat(1e-9).near_zero());
+        assert!(!Vec3::new(0.0, 0.0, 1e-7).near_zero());
+    }
+
+    #[test]
+    fn reflect_mirrors_about_the_normal() {
+        let n = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(Vec3::new(1.0, -1.0, 0.0).reflect(n), Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(Vec3::new(0.0, -1.0, 0.0).reflect(n), Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn refract_follows_snells_law() {
+        let n = Vec3::new(0.0, 1.0, 0.0);
+        // Head on, nothing bends.
+        assert_close(Vec3::new(0.0, -1.0, 0.0).refract(n, 1.5).unwrap(), Vec3::new(0.0, -1.0, 0.0));
+        // With equal indices, nothing bends at any angle.
+        let incoming = Vec3::new(1.0, -1.0, 0.0).normalize();
+        assert_close(incoming.refract(n, 1.0).unwrap(), incoming);
+
+        // Into glass at 45 degrees: sin(out) = sin(45) / 1.5.
+        let out = incoming.refract(n, 1.0 / 1.5).unwrap();
+        assert!((out.length() - 1.0).abs() < 1e-12);
+        assert!((out.x - std::f64::consts::FRAC_1_SQRT_2 / 1.5).abs() < 1e-12);
+        assert!(out.y < 0.0);
+
+        // Out of glass past the critical angle of about 41.8 degrees.
+        assert_eq!(incoming.refract(n, 1.5), None);
+    }
+
+    #[test]
+    fn lerp_and_component_extremes() {
+        let (a, b) = (Vec3::new(0.0, 10.0, -4.0), Vec3::new(2.0, 0.0, 4.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Vec3::new(0.5, 7.5, -2.0));
+        assert_eq!(a.min(b), Vec3::new(0.0, 0.0, -4.0));
+        assert_eq!(a.max(b), Vec3::new(2.0, 10.0, 4.0));
+        assert_eq!(a.min_element(), -4.0);
+        assert_eq!(a.max_element(), 10.0);
+    }
+
+    #[test]
+    fn converts_to_and_from_colors() {
+        let v = Vec3::from([0.25, 0.5, 1.0]);
+        assert_eq!(Color::from(v), Color::new(0.25, 0.5, 1.0));
+        assert_eq!(Vec3::from(Color::new(0.25, 0.5, 1.0)), v);
+    }
+}
@@ -0,0 +1,131 @@
+//! Participating media: smoke, fog and mist filling a shape.
+
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Isotropic;
+use crate::ray::Ray;
+use crate::rng::{mix, Rng};
+use crate::vec3::Vec3;
+
+/// A medium of constant density filling a closed shape. Rays passing through it
+/// are scattered at a random depth, more likely the denser it is.
+///
+/// The depth is drawn from a hash of the ray, so renders stay reproducible without
+/// threading a random number generator through every intersection test.
+#[derive(Debug, Clone)]
+pub struct Volume<H> {
+    pub boundary: H,
+    /// Scattering events per unit of distance.
+    pub density: f64,
+    pub phase: Isotropic,
+}
+
+impl<H: Hittable> Volume<H> {
+    pub fn new(boundary: H, density: f64, albedo: Color) -> Volume<H> {
+        Volume {
+            boundary,
+            density,
+            phase: Isotropic::new(albedo),
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Volume<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // Where the ray enters and leaves the boundary, wherever its origin is.
+        let enter = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY)?;
+        let leave = self.boundary.hit(ray, enter.t + 1e-4, f64::INFINITY)?;
+        let (enter, leave) = (enter.t.max(t_min).max(0.0), leave.t.min(t_max));
+        if enter >= leave {
+            return None;
+        }
+
+        let length = ray.direction.length();
+        let (o, d) = (ray.origin, ray.direction);
+        let seed = [o.x, o.y, o.z, d.x, d.y, d.z].iter().fold(0, |seed, c| mix(seed ^ c.to_bits()));
+        let distance = -(1.0 - Rng::new(seed).next_f64()).ln() / self.density;
+        if distance > (leave - enter) * length {
+            return None;
+        }
+
+        let t = enter + distance / length;
+        // Media have no surface, so the normal is arbitrary.
+        let mut hit = HitRecord::new(ray, t, Vec3::new(1.0, 0.0, 0.0), (0.0, 0.0));
+        hit.front_face = true;
+        hit.material = Some(&self.phase);
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Sphere;
+    use crate::vec3::Point3;
+
+    /// Directions of assorted lengths, none of them near zero.
+    fn directions(count: usize) -> impl Iterator<Item = Vec3> {
+        let mut rng = Rng::new(7);
+        (0..count).map(move |_| {
+            let d = Vec3::new(rng.next_f64() - 0.5, rng.next_f64() - 0.5, rng.next_f64() - 0.5);
+            d.normalize() * (0.5 + rng.next_f64() * 2.0)
+        })
+    }
+
+    #[test]
+    fn mean_free_path_is_the_inverse_density() {
+        // A medium deep enough that practically every ray scatters inside it.
+        let density = 4.0;
+        let fog = Volume::new(Sphere::new(Point3::ZERO, 100.0), density, Color::WHITE);
+        let count = 20_000;
+        let mut total = 0.0;
+        for d in directions(count) {
+            // Each ray enters the sphere 10 units from its origin.
+            let ray = Ray::new(Point3::ZERO - d.normalize() * 110.0, d);
+            let hit = fog.hit(&ray, 0.001, f64::INFINITY).expect("scattered inside");
+            total += hit.t * d.length() - 10.0;
+            assert!(hit.material.is_some() && hit.front_face);
+        }
+        let mean = total / count as f64;
+        // The standard error is 1 / (density * sqrt(count)), under 0.002.
+        assert!((mean - 1.0 / density).abs() < 0.01, "mean free path {}", mean);
+    }
+
+    #[test]
+    fn rays_from_inside_scatter_within_the_boundary() {
+        let density = 0.5;
+        let fog = Volume::new(Sphere::new(Point3::ZERO, 1.0), density, Color::WHITE);
+        let count = 20_000;
+        let mut scattered = 0;
+        for d in directions(count) {
+            let ray = Ray::new(Point3::ZERO, d);
+            if let Some(hit) = fog.hit(&ray, 0.001, f64::INFINITY) {
+                assert!(hit.t > 0.0 && hit.point.length() < 1.0, "{:?}", hit.point);
+                scattered += 1;
+            }
+        }
+        // Every ray crosses one unit of medium on its way out.
+        let expected = 1.0 - (-density).exp();
+        let fraction = scattered as f64 / count as f64;
+        assert!((fraction - expected).abs() < 0.02, "{} of rays scattered", fraction);
+    }
+
+    #[test]
+    fn empty_spans_never_scatter() {
+        let smoke = Volume::new(Sphere::new(Point3::ZERO, 1.0), 1e6, Color::WHITE);
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(smoke.hit(&ray, 0.001, f64::INFINITY).is_some());
+        assert!(smoke.hit(&ray, 5.0, 5.0).is_none());
+        // Spans that end before the medium or start after it.
+        assert!(smoke.hit(&ray, 0.001, 3.9).is_none());
+        assert!(smoke.hit(&ray, 6.1, f64::INFINITY).is_none());
+        // Rays that miss the boundary altogether.
+        let miss = Ray::new(Point3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(smoke.hit(&miss, 0.001, f64::INFINITY).is_none());
+    }
+}